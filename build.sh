@@ -1,2 +1,10 @@
 #!/bin/bash
-wasm-pack build --target web -d wasm/pkg
\ No newline at end of file
+set -e
+# fail early if the single-threaded build no longer compiles on stable
+RUSTUP_TOOLCHAIN=stable RUSTFLAGS="" cargo build --target wasm32-unknown-unknown \
+    --no-default-features --features blst,arkworks,console_error_panic_hook
+# multi-threaded package, built with the nightly toolchain and the `+atomics` flags of .cargo/config
+wasm-pack build --target web -d wasm/pkg
+# single-threaded package on stable, for pages that are not cross-origin isolated
+RUSTUP_TOOLCHAIN=stable RUSTFLAGS="" wasm-pack build --target web -d wasm/pkg-single-threaded -- \
+    --no-default-features --features blst,arkworks,console_error_panic_hook
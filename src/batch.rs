@@ -0,0 +1,142 @@
+use std::ops::Range;
+use ark_bls12_381::{Bls12_381, Fr, G1Projective, G2Affine};
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{One, PrimeField, Zero};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use kzg_ceremony_crypto::{Engine, G1, G2};
+use crate::parallel::prelude::*;
+use crate::points::{g1_from_bytes, g2_from_bytes};
+
+/// Witness entries folded into one multi-pairing. A failing batch is searched entry by entry
+const BATCH_SIZE: usize = 256;
+
+/**
+ * How the pubkey pairing of each witness entry is checked
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WitnessCheck {
+    /// One pairing check per entry, with the chosen engine
+    #[default]
+    PerEntry,
+    /// Random linear combinations of the entries, checked with a few arkworks
+    /// multi-pairings. Only a failing batch is checked entry by entry, with the chosen engine
+    Batched,
+}
+
+/**
+ * Index of the first entry of `range` whose running product does not follow
+ * from the previous one and its pubkey: `e(products[i], g2) == e(products[i - 1], pubkeys[i])`
+ */
+pub(crate) fn first_failing_pubkey<E: Engine>(
+    products: &[G1],
+    pubkeys: &[G2],
+    range: Range<usize>,
+    witness_check: WitnessCheck,
+) -> Option<usize> {
+    match witness_check {
+        WitnessCheck::PerEntry => first_failing_entry::<E>(products, pubkeys, range),
+        WitnessCheck::Batched => first_failing_batch::<E>(products, pubkeys, range, BATCH_SIZE),
+    }
+}
+
+fn first_failing_entry<E: Engine>(products: &[G1], pubkeys: &[G2], range: Range<usize>) -> Option<usize> {
+    range
+        .into_par_iter()
+        .find_first(| i | E::verify_pubkey(products[*i], products[*i - 1], pubkeys[*i]).is_err())
+}
+
+fn first_failing_batch<E: Engine>(products: &[G1], pubkeys: &[G2], range: Range<usize>, batch_size: usize) -> Option<usize> {
+    // the randomness is never revealed, so a forged transcript can't anticipate it
+    let mut seed = [0u8; 32];
+    if getrandom::getrandom(&mut seed).is_err() {
+        return first_failing_entry::<E>(products, pubkeys, range);
+    }
+    let batches: Vec<Range<usize>> = range
+        .clone()
+        .step_by(batch_size)
+        .map(| start | start..(start + batch_size).min(range.end))
+        .collect();
+    batches
+        .into_par_iter()
+        .filter(| batch | !batch_holds(products, pubkeys, batch.clone(), &seed))
+        .find_map_first(| batch | first_failing_entry::<E>(products, pubkeys, batch))
+}
+
+/// With random `r_i`, checks `e(sum(r_i * products[i]), g2) * prod(e(-r_i * products[i - 1], pubkeys[i])) == 1`.
+/// A single wrong entry passes with a probability of 2^-128
+fn batch_holds(products: &[G1], pubkeys: &[G2], batch: Range<usize>, seed: &[u8; 32]) -> bool {
+    let mut previous = match g1_from_bytes(&products[batch.start - 1]) {
+        Ok(previous) => previous,
+        Err(_) => return false,
+    };
+    let mut sum = G1Projective::zero();
+    let mut pairs = Vec::with_capacity(batch.len() + 1);
+    for i in batch {
+        let (product, pubkey) = match (g1_from_bytes(&products[i]), g2_from_bytes(&pubkeys[i])) {
+            (Ok(product), Ok(pubkey)) => (product, pubkey),
+            _ => return false,
+        };
+        let r = randomizer(seed, i);
+        sum += product.mul(r);
+        pairs.push(((-previous.mul(r)).into_affine().into(), pubkey.into()));
+        previous = product;
+    }
+    pairs.push((sum.into_affine().into(), G2Affine::prime_subgroup_generator().into()));
+    Bls12_381::product_of_pairings(&pairs).is_one()
+}
+
+/**
+ * Util functions
+ */
+/// 128 bits scalar derived from the secret seed and the witness index
+fn randomizer(seed: &[u8; 32], index: usize) -> <Fr as PrimeField>::BigInt {
+    let digest = Sha256::new()
+        .chain_update(seed)
+        .chain_update((index as u64).to_le_bytes())
+        .finalize();
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    Fr::from(u128::from_le_bytes(bytes)).into_repr()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kzg_ceremony_crypto::{DefaultEngine, Secret};
+
+    #[test]
+    fn batched_witness_check_test() {
+        // a valid chain of 20 contributions after the genesis entry
+        let mut products = vec![G1::one()];
+        let mut pubkeys = vec![G2::one()];
+        for seed in 1..=20u8 {
+            let tau = DefaultEngine::generate_tau(&Secret::new([seed; 32]));
+            let mut g1 = [G1::one(), *products.last().unwrap()];
+            DefaultEngine::add_tau_g1(&tau, &mut g1).unwrap();
+            let mut g2 = [G2::one(), G2::one()];
+            DefaultEngine::add_tau_g2(&tau, &mut g2).unwrap();
+            products.push(g1[1]);
+            pubkeys.push(g2[1]);
+        }
+        let range = 1..products.len();
+        assert_eq!(first_failing_batch::<DefaultEngine>(&products, &pubkeys, range.clone(), 6), None);
+        assert_eq!(first_failing_pubkey::<DefaultEngine>(&products, &pubkeys, range.clone(), WitnessCheck::Batched), None);
+
+        // the culprit is pinpointed across batches, like entry by entry
+        let mut broken = pubkeys.clone();
+        broken[15] = G2::one();
+        broken[9] = pubkeys[10];
+        for batch_size in [1, 6, 100] {
+            assert_eq!(first_failing_batch::<DefaultEngine>(&products, &broken, range.clone(), batch_size), Some(9));
+        }
+        assert_eq!(first_failing_entry::<DefaultEngine>(&products, &broken, range.clone()), Some(9));
+        assert_eq!(first_failing_batch::<DefaultEngine>(&products, &broken, 10..products.len(), 6), Some(15));
+
+        // swapped products break both of their entries
+        let mut swapped = products.clone();
+        swapped.swap(3, 4);
+        assert_eq!(first_failing_pubkey::<DefaultEngine>(&swapped, &pubkeys, range, WitnessCheck::Batched), Some(3));
+    }
+}
@@ -0,0 +1,362 @@
+use std::io::{Read, Write};
+use std::str::FromStr;
+use kzg_ceremony_crypto::{
+    G1,
+    G2,
+    Identity,
+    BlsSignature,
+    EcdsaSignature,
+    Powers,
+    Contribution,
+    BatchContribution,
+    Witness,
+    Transcript,
+    BatchTranscript,
+};
+use serde::Deserialize;
+use serde::de::IgnoredAny;
+use crate::{Result, WrapperError};
+
+/// First bytes of every binary file
+const MAGIC: [u8; 4] = *b"SPOT";
+/// Bumped on every incompatible change of the layout
+const VERSION: u8 = 1;
+const KIND_CONTRIBUTION: u8 = 1;
+const KIND_TRANSCRIPT: u8 = 2;
+/// Upper bound of the capacity reserved from untrusted lengths
+const MAX_RESERVED: usize = 1 << 16;
+
+/// Keys that tell a transcript from a contribution, the values are skipped
+#[derive(Deserialize)]
+struct TopLevelKeys {
+    transcripts: Option<IgnoredAny>,
+    contributions: Option<IgnoredAny>,
+}
+
+/**
+ * A batch contribution or transcript, as found in a JSON or binary file.
+ *
+ * The binary layout is the magic `SPOT`, a version byte, a kind byte
+ * (1 contribution, 2 transcript) and the shape: the number of sub-ceremonies
+ * then the numbers of G1 and G2 powers of each one. Points follow in their
+ * compressed form. Lengths are little-endian u32, witness arrays and strings
+ * are prefixed with theirs
+ */
+#[derive(Clone, Debug)]
+pub enum Batch {
+    Contribution(BatchContribution),
+    Transcript(BatchTranscript),
+}
+
+impl Batch {
+    /// True when the bytes start like a binary file
+    pub fn is_binary(bytes: &[u8]) -> bool {
+        bytes.starts_with(&MAGIC)
+    }
+
+    /// Parses a JSON batch transcript or contribution, told apart by their
+    /// `transcripts` or `contributions` key, so errors refer to the right one
+    pub fn from_json(bytes: &[u8]) -> Result<Self> {
+        let keys: TopLevelKeys = serde_json::from_slice(bytes)?;
+        match (keys.transcripts, keys.contributions) {
+            (Some(_), None) => Ok(Batch::Transcript(serde_json::from_slice(bytes)?)),
+            (None, Some(_)) => Ok(Batch::Contribution(serde_json::from_slice(bytes)?)),
+            _ => Err(<serde_json::Error as serde::de::Error>::custom(
+                "expected either a `transcripts` or a `contributions` key",
+            ).into()),
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        let json = match self {
+            Batch::Contribution(contribution) => serde_json::to_string(contribution)?,
+            Batch::Transcript(transcript) => serde_json::to_string(transcript)?,
+        };
+        Ok(json)
+    }
+
+    /// Parses a JSON or binary file, detected with its first bytes
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if Self::is_binary(bytes) {
+            Self::read_binary(bytes)
+        } else {
+            Self::from_json(bytes)
+        }
+    }
+
+    pub fn read_binary<R: Read>(mut reader: R) -> Result<Self> {
+        let magic: [u8; 4] = read_array(&mut reader)?;
+        if magic != MAGIC {
+            return Err(binary_error("not a binary ceremony file"));
+        }
+        let [version, kind] = read_array(&mut reader)?;
+        if version != VERSION {
+            return Err(binary_error(&format!("unsupported version {}", version)));
+        }
+
+        let num_sub_ceremonies = read_len(&mut reader)?;
+        let shape = (0..num_sub_ceremonies)
+            .map(| _ | Ok((read_len(&mut reader)?, read_len(&mut reader)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        match kind {
+            KIND_CONTRIBUTION => {
+                let contributions = shape
+                    .iter()
+                    .map(| (num_g1, num_g2) | Ok(Contribution {
+                        powers: read_powers(&mut reader, *num_g1, *num_g2)?,
+                        pot_pubkey: G2(read_array(&mut reader)?),
+                        bls_signature: read_bls_signature(&mut reader)?,
+                    }))
+                    .collect::<Result<Vec<_>>>()?;
+                let ecdsa_signature = read_ecdsa_signature(&mut reader)?;
+                Ok(Batch::Contribution(BatchContribution { contributions, ecdsa_signature }))
+            },
+            KIND_TRANSCRIPT => {
+                let transcripts = shape
+                    .iter()
+                    .map(| (num_g1, num_g2) | {
+                        let powers = read_powers(&mut reader, *num_g1, *num_g2)?;
+                        let witness = Witness {
+                            products: read_vec(&mut reader, | r | Ok(G1(read_array(r)?)))?,
+                            pubkeys: read_vec(&mut reader, | r | Ok(G2(read_array(r)?)))?,
+                            signatures: read_vec(&mut reader, read_bls_signature)?,
+                        };
+                        Ok(Transcript { powers, witness })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let participant_ids = read_vec(&mut reader, | r | {
+                    let id = read_string(r)?;
+                    Identity::from_str(&id).map_err(|error| WrapperError::Identity(error.to_string()))
+                })?;
+                let participant_ecdsa_signatures = read_vec(&mut reader, read_ecdsa_signature)?;
+                Ok(Batch::Transcript(BatchTranscript {
+                    transcripts,
+                    participant_ids,
+                    participant_ecdsa_signatures,
+                }))
+            },
+            _ => Err(binary_error(&format!("unknown kind {}", kind))),
+        }
+    }
+
+    pub fn write_binary<W: Write>(&self, mut writer: W) -> Result<()> {
+        writer.write_all(&MAGIC)?;
+        match self {
+            Batch::Contribution(batch) => {
+                writer.write_all(&[VERSION, KIND_CONTRIBUTION])?;
+                write_shape(&mut writer, batch.contributions.iter().map(| c | &c.powers))?;
+                for contribution in &batch.contributions {
+                    write_powers(&mut writer, &contribution.powers)?;
+                    writer.write_all(&contribution.pot_pubkey.0)?;
+                    write_bls_signature(&mut writer, &contribution.bls_signature)?;
+                }
+                write_ecdsa_signature(&mut writer, &batch.ecdsa_signature)?;
+            },
+            Batch::Transcript(batch) => {
+                writer.write_all(&[VERSION, KIND_TRANSCRIPT])?;
+                write_shape(&mut writer, batch.transcripts.iter().map(| t | &t.powers))?;
+                for transcript in &batch.transcripts {
+                    let witness = &transcript.witness;
+                    write_powers(&mut writer, &transcript.powers)?;
+                    write_vec(&mut writer, &witness.products, | w, product | Ok(w.write_all(&product.0)?))?;
+                    write_vec(&mut writer, &witness.pubkeys, | w, pubkey | Ok(w.write_all(&pubkey.0)?))?;
+                    write_vec(&mut writer, &witness.signatures, write_bls_signature)?;
+                }
+                write_vec(&mut writer, &batch.participant_ids, | w, id | write_string(w, &id.to_string()))?;
+                write_vec(&mut writer, &batch.participant_ecdsa_signatures, write_ecdsa_signature)?;
+            },
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/**
+ * Util functions
+ */
+fn binary_error(message: &str) -> WrapperError {
+    WrapperError::Binary(message.to_string())
+}
+
+fn read_array<R: Read, const N: usize>(reader: &mut R) -> Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    reader.read_exact(&mut bytes).map_err(|error| match error.kind() {
+        std::io::ErrorKind::UnexpectedEof => binary_error("unexpected end of file"),
+        _ => error.into(),
+    })?;
+    Ok(bytes)
+}
+
+fn read_len<R: Read>(reader: &mut R) -> Result<usize> {
+    Ok(u32::from_le_bytes(read_array(reader)?) as usize)
+}
+
+fn write_len<W: Write>(writer: &mut W, len: usize) -> Result<()> {
+    let len = u32::try_from(len).map_err(|_| binary_error("length does not fit in 32 bits"))?;
+    writer.write_all(&len.to_le_bytes())?;
+    Ok(())
+}
+
+/// Lengths come from the file, memory is only used as the items are actually read
+fn read_vec<R: Read, T>(reader: &mut R, read_item: impl Fn(&mut R) -> Result<T>) -> Result<Vec<T>> {
+    let len = read_len(reader)?;
+    let mut items = Vec::with_capacity(len.min(MAX_RESERVED));
+    for _ in 0..len {
+        items.push(read_item(reader)?);
+    }
+    Ok(items)
+}
+
+fn write_vec<W: Write, T>(writer: &mut W, items: &[T], write_item: impl Fn(&mut W, &T) -> Result<()>) -> Result<()> {
+    write_len(writer, items.len())?;
+    for item in items {
+        write_item(writer, item)?;
+    }
+    Ok(())
+}
+
+fn write_shape<'a, W: Write>(writer: &mut W, powers: impl ExactSizeIterator<Item = &'a Powers>) -> Result<()> {
+    write_len(writer, powers.len())?;
+    for powers in powers {
+        write_len(writer, powers.g1.len())?;
+        write_len(writer, powers.g2.len())?;
+    }
+    Ok(())
+}
+
+/// Powers have no length prefix, their lengths are in the header
+fn read_powers<R: Read>(reader: &mut R, num_g1: usize, num_g2: usize) -> Result<Powers> {
+    let mut g1 = Vec::with_capacity(num_g1.min(MAX_RESERVED));
+    for _ in 0..num_g1 {
+        g1.push(G1(read_array(reader)?));
+    }
+    let mut g2 = Vec::with_capacity(num_g2.min(MAX_RESERVED));
+    for _ in 0..num_g2 {
+        g2.push(G2(read_array(reader)?));
+    }
+    Ok(Powers { g1, g2 })
+}
+
+fn write_powers<W: Write>(writer: &mut W, powers: &Powers) -> Result<()> {
+    for point in &powers.g1 {
+        writer.write_all(&point.0)?;
+    }
+    for point in &powers.g2 {
+        writer.write_all(&point.0)?;
+    }
+    Ok(())
+}
+
+/// A presence byte, followed by the point when there is a signature
+fn read_bls_signature<R: Read>(reader: &mut R) -> Result<BlsSignature> {
+    let [is_present] = read_array(reader)?;
+    match is_present {
+        0 => Ok(BlsSignature(None)),
+        1 => Ok(BlsSignature(Some(G1(read_array(reader)?)))),
+        _ => Err(binary_error("invalid BLS signature flag")),
+    }
+}
+
+fn write_bls_signature<W: Write>(writer: &mut W, signature: &BlsSignature) -> Result<()> {
+    match &signature.0 {
+        None => writer.write_all(&[0])?,
+        Some(point) => {
+            writer.write_all(&[1])?;
+            writer.write_all(&point.0)?;
+        },
+    }
+    Ok(())
+}
+
+fn read_string<R: Read>(reader: &mut R) -> Result<String> {
+    let len = read_len(reader)?;
+    let mut bytes = Vec::with_capacity(len.min(MAX_RESERVED));
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != len {
+        return Err(binary_error("unexpected end of file"));
+    }
+    String::from_utf8(bytes).map_err(|_| binary_error("invalid UTF-8 string"))
+}
+
+fn write_string<W: Write>(writer: &mut W, string: &str) -> Result<()> {
+    write_len(writer, string.len())?;
+    writer.write_all(string.as_bytes())?;
+    Ok(())
+}
+
+/// Kept in its JSON string form, empty when the participant did not sign
+fn read_ecdsa_signature<R: Read>(reader: &mut R) -> Result<EcdsaSignature> {
+    let signature = read_string(reader)?;
+    Ok(serde_json::from_value(serde_json::Value::String(signature))?)
+}
+
+fn write_ecdsa_signature<W: Write>(writer: &mut W, signature: &EcdsaSignature) -> Result<()> {
+    match serde_json::to_value(signature)? {
+        serde_json::Value::String(signature) => write_string(writer, &signature),
+        _ => Err(binary_error("ECDSA signature is not a string")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_transcript() -> BatchTranscript {
+        let json = serde_json::json!({
+            "transcripts": [{
+                "numG1Powers": 4,
+                "numG2Powers": 2,
+                "powersOfTau": {
+                    "G1Powers": vec![G1::one(); 4],
+                    "G2Powers": vec![G2::one(); 2],
+                },
+                "witness": {
+                    "runningProducts": vec![G1::one(); 2],
+                    "potPubkeys": vec![G2::one(); 2],
+                    "blsSignatures": ["", G1::one()],
+                },
+            }],
+            "participantIds": [
+                "eth|0x000000000000000000000000000000000000dead",
+                "git|1234|someone",
+            ],
+            "participantEcdsaSignatures": ["", ""],
+        });
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn round_trip(batch: &Batch) -> Batch {
+        let mut bytes = Vec::new();
+        batch.write_binary(&mut bytes).unwrap();
+        assert!(Batch::is_binary(&bytes));
+        Batch::from_bytes(&bytes).unwrap()
+    }
+
+    #[test]
+    fn binary_round_trip_test() {
+        let transcript = Batch::Transcript(sample_transcript());
+        let json = transcript.to_json().unwrap();
+        assert!(matches!(Batch::from_bytes(json.as_bytes()).unwrap(), Batch::Transcript(_)));
+        assert_eq!(round_trip(&transcript).to_json().unwrap(), json);
+
+        let contribution = Batch::Contribution(sample_transcript().contribution());
+        let json = contribution.to_json().unwrap();
+        assert!(matches!(Batch::from_bytes(json.as_bytes()).unwrap(), Batch::Contribution(_)));
+        assert_eq!(round_trip(&contribution).to_json().unwrap(), json);
+
+        // a malformed transcript is reported as a transcript error
+        let mut malformed: serde_json::Value = serde_json::from_str(&transcript.to_json().unwrap()).unwrap();
+        malformed["participantIds"] = serde_json::json!([42]);
+        let error = Batch::from_json(malformed.to_string().as_bytes()).unwrap_err().to_string();
+        assert!(!error.contains("contributions"), "{}", error);
+        assert!(Batch::from_json(b"{}").is_err());
+
+        // truncated files and unknown versions are errors, not panics
+        let mut bytes = Vec::new();
+        transcript.write_binary(&mut bytes).unwrap();
+        assert!(matches!(Batch::read_binary(&bytes[..bytes.len() - 1]), Err(WrapperError::Binary(_))));
+        bytes[4] = VERSION + 1;
+        assert!(matches!(Batch::read_binary(&bytes[..]), Err(WrapperError::Binary(_))));
+    }
+}
@@ -0,0 +1,224 @@
+use std::fmt;
+use serde::Serialize;
+use kzg_ceremony_crypto::{BatchTranscript, Engine, Transcript};
+use crate::parallel::prelude::*;
+use crate::engine::with_engine;
+use crate::{
+    CeremonyParams,
+    CheckOutcome,
+    EcdsaSignaturesReport,
+    EngineKind,
+    Result,
+    VerificationReport,
+    WitnessCheck,
+    WrapperError,
+    verify_transcript_report,
+};
+
+/**
+ * A check whose outcome is not the same with every engine
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Divergence {
+    /// Like "transcript", "subgroup", "pairing" or "pubkey"
+    pub check: String,
+    pub sub_ceremony: Option<usize>,
+    pub witness_index: Option<usize>,
+    /// Outcome of each engine, in the order of the report `engines`
+    pub outcomes: Vec<String>,
+}
+
+/**
+ * Outcome of the transcript checks run by every engine, and where they disagree
+ */
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrossCheckReport {
+    pub engines: Vec<EngineKind>,
+    /// Verification report of each engine
+    pub reports: Vec<VerificationReport>,
+    pub divergences: Vec<Divergence>,
+}
+
+impl CrossCheckReport {
+    pub fn is_consistent(&self) -> bool {
+        self.divergences.is_empty()
+    }
+
+    /// Every engine agrees that the transcript is valid
+    pub fn is_valid(&self) -> bool {
+        self.is_consistent() && self.reports.iter().all(| report | report.is_valid)
+    }
+
+    /// Adds the optional ECDSA audit to the report of every engine, it does not depend on them
+    pub fn with_ecdsa_signatures(mut self, report: EcdsaSignaturesReport) -> Self {
+        self.reports = self
+            .reports
+            .into_iter()
+            .map(| engine_report | engine_report.with_ecdsa_signatures(report.clone()))
+            .collect();
+        self
+    }
+}
+
+/// What an engine found, with the intermediate results that are compared
+struct EngineRun {
+    engine: EngineKind,
+    /// Outcome of the `verify_self` of kzg-ceremony-crypto
+    verified: CheckOutcome,
+    report: VerificationReport,
+    /// Pairing check of every witness pubkey, per sub-ceremony
+    pubkey_pairings: Vec<Vec<bool>>,
+}
+
+/**
+ * Runs the transcript checks with every engine of the build, `blst` and `arkworks`,
+ * and compares their outcomes, down to the subgroup check of each sub-ceremony and
+ * the pairing check of each witness pubkey
+ */
+pub fn cross_check_transcript(batch_transcript: &BatchTranscript, params: &CeremonyParams) -> Result<CrossCheckReport> {
+    let engines = EngineKind::available();
+    if engines.len() < 2 {
+        return Err(WrapperError::Engine(String::from("the cross-check needs both the blst and arkworks features")));
+    }
+    // one engine at a time, each of them already uses every thread
+    let runs = engines
+        .into_iter()
+        .map(| engine | with_engine!(engine, E => run_engine::<E>(engine, batch_transcript, params)))
+        .collect();
+    Ok(compare(runs))
+}
+
+fn run_engine<E: Engine>(engine: EngineKind, batch_transcript: &BatchTranscript, params: &CeremonyParams) -> EngineRun {
+    EngineRun {
+        engine,
+        verified: CheckOutcome::from_result(batch_transcript.verify_self::<E>(params.sizes().to_vec())),
+        report: verify_transcript_report::<E>(batch_transcript, params, WitnessCheck::PerEntry),
+        pubkey_pairings: batch_transcript.transcripts.iter().map(pubkey_pairings::<E>).collect(),
+    }
+}
+
+/// Unlike the report, which stops at the first failing pubkey, every entry is checked
+fn pubkey_pairings<E: Engine>(transcript: &Transcript) -> Vec<bool> {
+    let (products, pubkeys) = (&transcript.witness.products, &transcript.witness.pubkeys);
+    (1..products.len().min(pubkeys.len()))
+        .into_par_iter()
+        .map(| i | E::verify_pubkey(products[i], products[i - 1], pubkeys[i]).is_ok())
+        .collect()
+}
+
+fn compare(runs: Vec<EngineRun>) -> CrossCheckReport {
+    let mut divergences = Vec::new();
+    let mut compare_outcomes = | check: &str, sub_ceremony: Option<usize>, witness_index: Option<usize>, outcomes: Vec<(bool, String)> | {
+        if outcomes.iter().any(| (passed, _) | *passed != outcomes[0].0) {
+            divergences.push(Divergence {
+                check: check.to_string(),
+                sub_ceremony,
+                witness_index,
+                outcomes: outcomes.into_iter().map(| (_, outcome) | outcome).collect(),
+            });
+        }
+    };
+    let describe = | outcome: &CheckOutcome | (outcome.is_passed(), outcome.to_string());
+
+    compare_outcomes("transcript", None, None, runs.iter().map(| run | describe(&run.verified)).collect());
+    let num_sub_ceremonies = runs[0].report.sub_ceremonies.len();
+    for index in 0..num_sub_ceremonies {
+        let sub_ceremonies: Vec<_> = runs.iter().map(| run | &run.report.sub_ceremonies[index]).collect();
+        compare_outcomes("subgroup", Some(index), None, sub_ceremonies.iter().map(| report | describe(&report.subgroup_check)).collect());
+        compare_outcomes("pairing", Some(index), None, sub_ceremonies.iter().map(| report | describe(&report.pairing_check)).collect());
+
+        let num_pubkeys = runs[0].pubkey_pairings[index].len();
+        for position in 0..num_pubkeys {
+            let outcomes = runs
+                .iter()
+                .map(| run | {
+                    let passed = run.pubkey_pairings[index][position];
+                    (passed, String::from(if passed { "passed" } else { "failed" }))
+                })
+                .collect();
+            compare_outcomes("pubkey", Some(index), Some(position + 1), outcomes);
+        }
+    }
+
+    CrossCheckReport {
+        engines: runs.iter().map(| run | run.engine).collect(),
+        reports: runs.into_iter().map(| run | run.report).collect(),
+        divergences,
+    }
+}
+
+impl fmt::Display for CrossCheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (engine, report) in self.engines.iter().zip(&self.reports) {
+            writeln!(f, "== {}", engine)?;
+            writeln!(f, "{}", report)?;
+        }
+        if self.is_consistent() {
+            return write!(f, "The engines agree on every check");
+        }
+        writeln!(f, "The engines DISAGREE on {} checks:", self.divergences.len())?;
+        for divergence in &self.divergences {
+            write!(f, "  {}", divergence.check)?;
+            if let Some(index) = divergence.sub_ceremony {
+                write!(f, " of sub-ceremony {}", index)?;
+            }
+            if let Some(index) = divergence.witness_index {
+                write!(f, " at witness index {}", index)?;
+            }
+            let outcomes: Vec<_> = self
+                .engines
+                .iter()
+                .zip(&divergence.outcomes)
+                .map(| (engine, outcome) | format!("{} {}", engine, outcome))
+                .collect();
+            writeln!(f, ": {}", outcomes.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kzg_ceremony_crypto::{DefaultEngine, Identity, Secret};
+
+    #[test]
+    fn compare_engines_test() {
+        let params = CeremonyParams::new(vec![(4, 2), (8, 3)]).unwrap();
+        let mut batch_transcript = BatchTranscript::new(params.sizes());
+        let identity: Identity = "git|1|alice".parse().unwrap();
+        let mut contribution = batch_transcript.contribution();
+        contribution.add_entropy::<DefaultEngine>(&Secret::from([1; 32]), &identity).unwrap();
+        for (transcript, contribution) in batch_transcript.transcripts.iter_mut().zip(contribution.contributions) {
+            transcript.witness.products.push(contribution.powers.g1[1]);
+            transcript.witness.pubkeys.push(contribution.pot_pubkey);
+            transcript.witness.signatures.push(contribution.bls_signature);
+            transcript.powers = contribution.powers;
+        }
+        batch_transcript.participant_ids.push(identity);
+
+        let run = | engine | run_engine::<DefaultEngine>(engine, &batch_transcript, &params);
+        let report = compare(vec![run(EngineKind::default()), run(EngineKind::default())]);
+        assert!(report.is_valid(), "{}", report);
+        assert_eq!(report.reports.len(), 2);
+
+        // a backend accepting a bad pubkey or a point outside the subgroup is caught
+        let mut lenient = run(EngineKind::default());
+        lenient.pubkey_pairings[1][0] = false;
+        lenient.report.sub_ceremonies[0].subgroup_check = CheckOutcome::Failed { error: String::from("bug") };
+        let report = compare(vec![run(EngineKind::default()), lenient]);
+        assert!(!report.is_consistent() && !report.is_valid());
+        let checks: Vec<_> = report
+            .divergences
+            .iter()
+            .map(| divergence | (divergence.check.as_str(), divergence.sub_ceremony, divergence.witness_index))
+            .collect();
+        assert_eq!(checks, vec![("subgroup", Some(0), None), ("pubkey", Some(1), Some(1))]);
+        assert_eq!(report.divergences[1].outcomes, vec!["passed", "failed"]);
+
+        let result = cross_check_transcript(&batch_transcript, &params);
+        assert_eq!(result.is_ok(), EngineKind::available().len() == 2);
+    }
+}
@@ -0,0 +1,282 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use kzg_ceremony_crypto::{BatchTranscript, G2, Identity};
+use crate::parallel::prelude::*;
+use crate::eth::{address_to_string, keccak256, recover_address};
+use crate::{Result, WrapperError};
+
+/// Domain of the Ethereum KZG ceremony signatures
+const DOMAIN_NAME: &str = "Ethereum KZG Ceremony";
+const DOMAIN_VERSION: &str = "1.0";
+const CHAIN_ID: u64 = 1;
+
+const DOMAIN_TYPE: &str = "EIP712Domain(string name,string version,uint256 chainId)";
+const PUBKEY_TYPE: &str = "contributionPubkey(uint256 numG1Powers,uint256 numG2Powers,bytes potPubkey)";
+const PUBKEYS_TYPE: &str = "PoTPubkeys(contributionPubkey[] potPubkeys)";
+
+/**
+ * Typed data an Ethereum participant signs with `eth_signTypedData_v4`:
+ * the potPubkey of each sub-ceremony with its number of powers
+ */
+pub fn pot_pubkeys_typed_data(sizes: &[(usize, usize)], pot_pubkeys: &[G2]) -> Value {
+    let pot_pubkeys: Vec<Value> = sizes
+        .iter()
+        .zip(pot_pubkeys)
+        .map(| ((num_g1, num_g2), pot_pubkey) | json!({
+            "numG1Powers": num_g1,
+            "numG2Powers": num_g2,
+            "potPubkey": pot_pubkey,
+        }))
+        .collect();
+    json!({
+        "types": {
+            "EIP712Domain": [
+                { "name": "name", "type": "string" },
+                { "name": "version", "type": "string" },
+                { "name": "chainId", "type": "uint256" },
+            ],
+            "contributionPubkey": [
+                { "name": "numG1Powers", "type": "uint256" },
+                { "name": "numG2Powers", "type": "uint256" },
+                { "name": "potPubkey", "type": "bytes" },
+            ],
+            "PoTPubkeys": [
+                { "name": "potPubkeys", "type": "contributionPubkey[]" },
+            ],
+        },
+        "primaryType": "PoTPubkeys",
+        "domain": { "name": DOMAIN_NAME, "version": DOMAIN_VERSION, "chainId": CHAIN_ID },
+        "message": { "potPubkeys": pot_pubkeys },
+    })
+}
+
+/// Digest the wallet signs for the typed data above
+pub fn pot_pubkeys_digest(sizes: &[(usize, usize)], pot_pubkeys: &[G2]) -> [u8; 32] {
+    let domain_separator = keccak256(&[
+        keccak256(DOMAIN_TYPE.as_bytes()),
+        keccak256(DOMAIN_NAME.as_bytes()),
+        keccak256(DOMAIN_VERSION.as_bytes()),
+        uint256(CHAIN_ID),
+    ].concat());
+
+    let pubkey_type_hash = keccak256(PUBKEY_TYPE.as_bytes());
+    let pubkey_hashes: Vec<u8> = sizes
+        .iter()
+        .zip(pot_pubkeys)
+        .flat_map(| ((num_g1, num_g2), pot_pubkey) | keccak256(&[
+            pubkey_type_hash,
+            uint256(*num_g1 as u64),
+            uint256(*num_g2 as u64),
+            keccak256(&pot_pubkey.0),
+        ].concat()))
+        .collect();
+    // referenced struct types are appended to the type of the message
+    let message_type_hash = keccak256(format!("{}{}", PUBKEYS_TYPE, PUBKEY_TYPE).as_bytes());
+    let message_hash = keccak256(&[message_type_hash, keccak256(&pubkey_hashes)].concat());
+
+    keccak256(&[&[0x19, 0x01][..], &domain_separator, &message_hash].concat())
+}
+
+/**
+ * Checks that the potPubkeys were signed by the address of an `eth|` identity
+ */
+pub fn verify_pot_pubkeys_signature(
+    sizes: &[(usize, usize)],
+    pot_pubkeys: &[G2],
+    signature: &str,
+    identity: &Identity,
+) -> Result<bool> {
+    let address = match identity {
+        Identity::Ethereum { address } => address,
+        _ => return Err(WrapperError::Identity(format!("{} is not an Ethereum identity", identity))),
+    };
+    let signer = recover_address(&pot_pubkeys_digest(sizes, pot_pubkeys), signature)?;
+    Ok(signer == *address)
+}
+
+/**
+ * Outcome of checking the ECDSA signatures of the `eth|` participants of a transcript,
+ * by witness index
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EcdsaSignaturesReport {
+    /// Signatures recovering the address of the participant
+    pub valid: Vec<usize>,
+    /// Ethereum participants who did not sign their potPubkeys
+    pub absent: Vec<usize>,
+    pub mismatches: Vec<EcdsaMismatch>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EcdsaMismatch {
+    pub witness_index: usize,
+    pub identity: String,
+    /// Address recovered from the signature, `None` when it is malformed
+    pub signer: Option<String>,
+}
+
+impl EcdsaSignaturesReport {
+    /// True when no signature recovers another address
+    pub fn is_valid(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/**
+ * Rebuilds the signed message of every `eth|` participant from their potPubkeys
+ * across all sub-ceremonies, and compares the recovered signer with their address
+ */
+pub fn verify_ecdsa_signatures(batch_transcript: &BatchTranscript) -> Result<EcdsaSignaturesReport> {
+    let num_participants = batch_transcript.participant_ids.len();
+    if batch_transcript.participant_ecdsa_signatures.len() != num_participants
+        || batch_transcript.transcripts.iter().any(| transcript | transcript.witness.pubkeys.len() != num_participants)
+    {
+        return Err(WrapperError::Witness(format!(
+            "the ECDSA signatures and pubkeys should match the {} participants", num_participants,
+        )));
+    }
+    let sizes: Vec<_> = batch_transcript
+        .transcripts
+        .iter()
+        .map(| transcript | (transcript.powers.g1.len(), transcript.powers.g2.len()))
+        .collect();
+    let signatures = batch_transcript
+        .participant_ecdsa_signatures
+        .iter()
+        .map(serde_json::to_value)
+        .collect::<serde_json::Result<Vec<_>>>()?;
+
+    let outcomes: Vec<_> = batch_transcript
+        .participant_ids
+        .par_iter()
+        .zip(signatures.par_iter())
+        .enumerate()
+        .filter_map(| (witness_index, (identity, signature)) | {
+            let address = match identity {
+                Identity::Ethereum { address } => address,
+                _ => return None,
+            };
+            let signature = match signature.as_str() {
+                Some(signature) if !signature.is_empty() => signature,
+                _ => return Some((witness_index, None)),
+            };
+            let pot_pubkeys: Vec<G2> = batch_transcript
+                .transcripts
+                .iter()
+                .map(| transcript | transcript.witness.pubkeys[witness_index])
+                .collect();
+            let signer = recover_address(&pot_pubkeys_digest(&sizes, &pot_pubkeys), signature).ok();
+            let mismatch = match signer {
+                Some(signer) if signer == *address => None,
+                _ => Some(EcdsaMismatch {
+                    witness_index,
+                    identity: identity.to_string(),
+                    signer: signer.as_ref().map(address_to_string),
+                }),
+            };
+            Some((witness_index, Some(mismatch)))
+        })
+        .collect();
+
+    let mut report = EcdsaSignaturesReport::default();
+    for (witness_index, outcome) in outcomes {
+        match outcome {
+            None => report.absent.push(witness_index),
+            Some(None) => report.valid.push(witness_index),
+            Some(Some(mismatch)) => report.mismatches.push(mismatch),
+        }
+    }
+    Ok(report)
+}
+
+/**
+ * Util functions
+ */
+fn uint256(value: u64) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes[24..].copy_from_slice(&value.to_be_bytes());
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k256::ecdsa::SigningKey;
+    use kzg_ceremony_crypto::{DefaultEngine, EcdsaSignature, Secret, get_pot_pubkeys};
+    use crate::eth::{address_of, address_to_string, sign_digest};
+
+    #[test]
+    fn pot_pubkeys_signature_test() {
+        let sizes = [(4096, 65), (8192, 65), (16384, 65), (32768, 65)];
+        let pot_pubkeys = get_pot_pubkeys::<DefaultEngine>(&Secret::from([1; 32]));
+        let key = SigningKey::from_bytes(&[7; 32]).unwrap();
+        let identity: Identity = format!("eth|{}", address_to_string(&address_of(&key.verifying_key()))).parse().unwrap();
+
+        let typed_data = pot_pubkeys_typed_data(&sizes, &pot_pubkeys);
+        assert_eq!(typed_data["message"]["potPubkeys"][3]["numG1Powers"], 32768);
+        assert_eq!(typed_data["message"]["potPubkeys"][0]["potPubkey"], json!(pot_pubkeys[0]));
+
+        let signature = sign_digest(&key, &pot_pubkeys_digest(&sizes, &pot_pubkeys));
+        assert!(verify_pot_pubkeys_signature(&sizes, &pot_pubkeys, &signature, &identity).unwrap());
+
+        // other pubkeys, another signer or a GitHub identity are rejected
+        let other_pubkeys = get_pot_pubkeys::<DefaultEngine>(&Secret::from([2; 32]));
+        assert!(!verify_pot_pubkeys_signature(&sizes, &other_pubkeys, &signature, &identity).unwrap());
+        let other_identity: Identity = "eth|0x000000000000000000000000000000000000dead".parse().unwrap();
+        assert!(!verify_pot_pubkeys_signature(&sizes, &pot_pubkeys, &signature, &other_identity).unwrap());
+        let github: Identity = "git|1|alice".parse().unwrap();
+        let result = verify_pot_pubkeys_signature(&sizes, &pot_pubkeys, &signature, &github);
+        assert!(matches!(result, Err(WrapperError::Identity(_))));
+
+        // signed with a wallet during a test ceremony
+        let pot_pubkeys: Vec<G2> = serde_json::from_value(json!([
+            "0xb60f0783433e610a3299d8c7e021f1d9201ff3945e86cdb1887b7799dde67f51dbba932100bdc504fd3c43748ef244db16c1ed2975ce432c21ce64d9795367a901468930b4e5e53501532251445de13e81be7f6c4e1381ba669c26c48f2cfdff",
+            "0x8705d24df98de27dc342c8d22e779cfaea5973298e1bc59d35501da3ff5c0ba61b36a4f8c244a7cc8ac124cfd96b4f950f9c00b0268bc58c73b31fa4d167f221050b768b6847732d39eb6adba94afa53d50363b51d07ae0928b23f9a3173a6ec",
+            "0xb9a2e47063ecd62734dfcff179e38fa540bf0c8bcf57cbed0aa36afccaba84a8e53368f68cce6b0714793130c8be1daf120d776790a583ff0baf241609e491d9a9b740b32e5c0ba18b82b7a14745f7d555c49f2edde4ebff04dac7df843e3c02",
+            "0xa8b550a52a4512793a2d248daa9a590d8d94da6546244cd4535f1e53868a37eef9c01c75dccfc1ce13e0d5bcbb92528811a7a5320152822a9b82a9803ed4dfe28aacf32d9e911fb39a59a7da09809f4f18d92e75485dcea5f2fcb353cb864cb2",
+        ])).unwrap();
+        let signature = "0x1ba9cc2a6a16b43b14c853ce7afdeee3b2a82a0a3780ab550ed9edd9ff16c3611e0ef03fd13f35a924899b4eccde2c8ab9b3c90ab30b4fb8a756b3c25d447e091c";
+        let identity: Identity = "eth|0x447027e9ca54247f4972a18a87232b16b1a57598".parse().unwrap();
+        assert!(verify_pot_pubkeys_signature(&sizes, &pot_pubkeys, signature, &identity).unwrap());
+    }
+
+    #[test]
+    fn verify_ecdsa_signatures_test() {
+        let sizes = [(4, 2), (8, 3)];
+        let mut batch_transcript = BatchTranscript::new(&sizes);
+        let keys: Vec<SigningKey> = (1..=3u8).map(| seed | SigningKey::from_bytes(&[seed; 32]).unwrap()).collect();
+        let eth = | key: &SigningKey | format!("eth|{}", address_to_string(&address_of(&key.verifying_key())));
+        // signed, unsigned, signed by another account, GitHub
+        let participants = [
+            (eth(&keys[0]), Some(&keys[0])),
+            (eth(&keys[1]), None),
+            (eth(&keys[2]), Some(&keys[0])),
+            (String::from("git|1|alice"), None),
+        ];
+        for (seed, (identity, key)) in participants.iter().enumerate() {
+            let mut pot_pubkeys = get_pot_pubkeys::<DefaultEngine>(&Secret::from([seed as u8; 32]));
+            pot_pubkeys.truncate(sizes.len());
+            let signature = key.map_or_else(String::new, | key | sign_digest(key, &pot_pubkeys_digest(&sizes, &pot_pubkeys)));
+            for (transcript, pot_pubkey) in batch_transcript.transcripts.iter_mut().zip(&pot_pubkeys) {
+                transcript.witness.pubkeys.push(*pot_pubkey);
+            }
+            batch_transcript.participant_ids.push(identity.parse().unwrap());
+            batch_transcript.participant_ecdsa_signatures.push(serde_json::from_value::<EcdsaSignature>(signature.into()).unwrap());
+        }
+
+        let report = verify_ecdsa_signatures(&batch_transcript).unwrap();
+        assert_eq!(report.valid, vec![1]);
+        assert_eq!(report.absent, vec![2]);
+        assert_eq!(report.mismatches, vec![EcdsaMismatch {
+            witness_index: 3,
+            identity: participants[2].0.clone(),
+            signer: Some(participants[0].0.replace("eth|", "")),
+        }]);
+        assert!(!report.is_valid());
+
+        batch_transcript.participant_ecdsa_signatures.pop();
+        assert!(matches!(verify_ecdsa_signatures(&batch_transcript), Err(WrapperError::Witness(_))));
+    }
+}
@@ -0,0 +1,110 @@
+use std::fmt;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use crate::{Result, WrapperError};
+
+#[cfg(not(any(feature = "blst", feature = "arkworks")))]
+compile_error!("enable the `blst` or the `arkworks` feature, or both");
+
+/**
+ * Backend of the BLS12-381 operations, among the ones enabled by the cargo features
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EngineKind {
+    /// The `blst` C library, the fastest, but it needs a C toolchain
+    #[cfg(feature = "blst")]
+    Blst,
+    /// Pure Rust arkworks
+    #[cfg(feature = "arkworks")]
+    Arkworks,
+}
+
+impl EngineKind {
+    /// The engines this build was compiled with
+    pub fn available() -> Vec<Self> {
+        vec![
+            #[cfg(feature = "blst")]
+            EngineKind::Blst,
+            #[cfg(feature = "arkworks")]
+            EngineKind::Arkworks,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "blst")]
+            EngineKind::Blst => "blst",
+            #[cfg(feature = "arkworks")]
+            EngineKind::Arkworks => "arkworks",
+        }
+    }
+}
+
+/// `blst` when it is enabled, as before the engine could be chosen
+impl Default for EngineKind {
+    fn default() -> Self {
+        Self::available()[0]
+    }
+}
+
+impl fmt::Display for EngineKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Parses `blst` or `arkworks`, if it was compiled in
+impl FromStr for EngineKind {
+    type Err = WrapperError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some(engine) = Self::available().into_iter().find(| engine | engine.name().eq_ignore_ascii_case(s)) {
+            return Ok(engine);
+        }
+        let known = ["blst", "arkworks"];
+        if known.iter().any(| name | name.eq_ignore_ascii_case(s)) {
+            Err(WrapperError::Engine(format!("{} is not enabled in this build", s)))
+        } else {
+            Err(WrapperError::Engine(format!("unknown engine {:?}, expected one of {:?}", s, known)))
+        }
+    }
+}
+
+/// Runs `$body` with `$engine` bound to the engine type of `$kind`
+macro_rules! with_engine {
+    ($kind:expr, $engine:ident => $body:expr) => {
+        match $kind {
+            #[cfg(feature = "blst")]
+            $crate::EngineKind::Blst => {
+                type $engine = kzg_ceremony_crypto::BLST;
+                $body
+            },
+            #[cfg(feature = "arkworks")]
+            $crate::EngineKind::Arkworks => {
+                type $engine = kzg_ceremony_crypto::Arkworks;
+                $body
+            },
+        }
+    };
+}
+pub(crate) use with_engine;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kzg_ceremony_crypto::{Secret, get_pot_pubkeys};
+
+    #[test]
+    fn engine_kind_test() {
+        for engine in EngineKind::available() {
+            assert_eq!(engine.to_string().parse::<EngineKind>().unwrap(), engine);
+            assert_eq!(serde_json::to_string(&engine).unwrap(), format!("{:?}", engine.name()));
+            // every engine derives the same pubkeys
+            let pubkeys = with_engine!(engine, E => get_pot_pubkeys::<E>(&Secret::from([1; 32])));
+            let expected = with_engine!(EngineKind::default(), E => get_pot_pubkeys::<E>(&Secret::from([1; 32])));
+            assert_eq!(pubkeys, expected);
+        }
+        assert!(matches!("BLS".parse::<EngineKind>(), Err(WrapperError::Engine(_))));
+    }
+}
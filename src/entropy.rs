@@ -0,0 +1,221 @@
+use std::{fs, path::Path, str::FromStr};
+use argon2::{Algorithm, Argon2, Version};
+use secrecy::{ExposeSecret, Secret, Zeroize};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::{Result, WrapperError};
+
+/// Domain separation tag, so the secret can't collide with other uses of these inputs
+const DOMAIN_SEPARATOR: &[u8] = b"wrapper-small-pot/entropy/v1";
+/// Bytes requested to the OS random number generator
+const OS_RANDOMNESS_LEN: usize = 64;
+
+/**
+ * Mouse position sampled by the frontend
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub struct MouseSample {
+    pub x: f64,
+    pub y: f64,
+    /// Timestamp of the sample, like `performance.now()`
+    pub time: f64,
+}
+
+/**
+ * Costs of the Argon2id derivation of a passphrase
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct KdfParams {
+    /// Memory used by the derivation, in KiB
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    /// 64 MiB and 3 iterations, affordable in a browser
+    fn default() -> Self {
+        Self { memory_kib: 65536, iterations: 3, parallelism: 1 }
+    }
+}
+
+/// Parses `default` or costs like `m=65536,t=3,p=1`, missing costs keep their default
+impl FromStr for KdfParams {
+    type Err = WrapperError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut params = Self::default();
+        if s.eq_ignore_ascii_case("default") {
+            return Ok(params);
+        }
+        for cost in s.split(',') {
+            let (key, value) = cost.trim().split_once('=')
+            .ok_or_else(|| WrapperError::Entropy(format!("expected <key>=<value>, found {:?}", cost)))?;
+            let value = value.parse()
+            .map_err(|_| WrapperError::Entropy(format!("invalid KDF cost {:?}", value)))?;
+            match key {
+                "m" => params.memory_kib = value,
+                "t" => params.iterations = value,
+                "p" => params.parallelism = value,
+                _ => return Err(WrapperError::Entropy(format!("unknown KDF cost {:?}, expected m, t or p", key))),
+            }
+        }
+        Ok(params)
+    }
+}
+
+/**
+ * Mixes several entropy sources into the 32 bytes secret of a contribution.
+ * Every source is hashed with its label and length, so different
+ * sources can never be confused with each other
+ */
+pub struct EntropyMixer {
+    hasher: Sha256,
+    num_sources: usize,
+}
+
+impl EntropyMixer {
+    pub fn new() -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(DOMAIN_SEPARATOR);
+        Self { hasher, num_sources: 0 }
+    }
+
+    /// Text typed by the participant
+    pub fn add_text(&mut self, text: &str) -> &mut Self {
+        self.absorb(b"text", text.as_bytes())
+    }
+
+    /// Mouse movements of the participant
+    pub fn add_mouse_samples(&mut self, samples: &[MouseSample]) -> &mut Self {
+        let mut bytes: Vec<u8> = samples
+            .iter()
+            .flat_map(| sample | [sample.x, sample.y, sample.time])
+            .flat_map(f64::to_le_bytes)
+            .collect();
+        self.absorb(b"mouse", &bytes);
+        bytes.zeroize();
+        self
+    }
+
+    /// Randomness of the OS, `crypto.getRandomValues` in browsers
+    pub fn add_os_randomness(&mut self) -> Result<&mut Self> {
+        let mut bytes = [0u8; OS_RANDOMNESS_LEN];
+        getrandom::getrandom(&mut bytes)
+        .map_err(|error| WrapperError::Entropy(error.to_string()))?;
+        self.absorb(b"os", &bytes);
+        bytes.zeroize();
+        Ok(self)
+    }
+
+    /// Content of a file, like a dump of a hardware random number generator
+    pub fn add_file(&mut self, path: &Path) -> Result<&mut Self> {
+        let mut bytes = fs::read(path)?;
+        self.absorb(b"file", &bytes);
+        bytes.zeroize();
+        Ok(self)
+    }
+
+    /// Passphrase hardened with Argon2id, salted with the sources added before it.
+    /// Add the OS randomness first: guessing the passphrase then also needs that randomness,
+    /// and even when it leaks every guess still costs a memory-hard derivation
+    pub fn add_passphrase(&mut self, passphrase: &str, params: &KdfParams) -> Result<&mut Self> {
+        let argon2_params = argon2::Params::new(params.memory_kib, params.iterations, params.parallelism, Some(32))
+        .map_err(|error| WrapperError::Entropy(format!("invalid KDF params: {}", error)))?;
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, argon2_params);
+
+        let salt: [u8; 32] = self.hasher.clone().finalize().into();
+        let mut key = [0u8; 32];
+        argon2.hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+        .map_err(|error| WrapperError::Entropy(error.to_string()))?;
+        self.absorb(b"passphrase", &key);
+        key.zeroize();
+        Ok(self)
+    }
+
+    /// Derives the secret from every source added so far
+    pub fn finalize(self) -> Result<Secret<[u8; 32]>> {
+        if self.num_sources == 0 {
+            return Err(WrapperError::Entropy(String::from("no entropy source was added")));
+        }
+        let secret: [u8; 32] = self.hasher.finalize().into();
+        Ok(Secret::new(secret))
+    }
+
+    /// Same as `finalize`, encoded as the hex string taken by the other functions
+    pub fn finalize_to_string(self) -> Result<String> {
+        let secret = self.finalize()?;
+        Ok(hex::encode(secret.expose_secret()))
+    }
+
+    fn absorb(&mut self, label: &[u8], data: &[u8]) -> &mut Self {
+        self.hasher.update((label.len() as u64).to_le_bytes());
+        self.hasher.update(label);
+        self.hasher.update((data.len() as u64).to_le_bytes());
+        self.hasher.update(data);
+        self.num_sources += 1;
+        self
+    }
+}
+
+impl Default for EntropyMixer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mix(texts: &[&str]) -> [u8; 32] {
+        let mut mixer = EntropyMixer::new();
+        for text in texts {
+            mixer.add_text(text);
+        }
+        *mixer.finalize().unwrap().expose_secret()
+    }
+
+    #[test]
+    fn entropy_mixing_test() {
+        // deterministic without OS randomness
+        assert_eq!(mix(&["foo"]), mix(&["foo"]));
+        // sources are length prefixed
+        assert_ne!(mix(&["ab", "c"]), mix(&["a", "bc"]));
+        // sources are labeled
+        let mut mixer = EntropyMixer::new();
+        mixer.add_mouse_samples(&[]);
+        assert_ne!(*mixer.finalize().unwrap().expose_secret(), mix(&[""]));
+
+        let mut first = EntropyMixer::new();
+        first.add_text("foo").add_os_randomness().unwrap();
+        let mut second = EntropyMixer::new();
+        second.add_text("foo").add_os_randomness().unwrap();
+        assert_ne!(first.finalize_to_string().unwrap(), second.finalize_to_string().unwrap());
+
+        assert!(matches!(EntropyMixer::new().finalize(), Err(WrapperError::Entropy(_))));
+    }
+
+    #[test]
+    fn passphrase_kdf_test() {
+        let params = KdfParams::from_str("m=64,t=1").unwrap();
+        assert_eq!(params, KdfParams { memory_kib: 64, iterations: 1, parallelism: 1 });
+        assert!(KdfParams::from_str("m=64,x=1").is_err());
+        assert!(KdfParams::from_str("m").is_err());
+
+        let derive = | salt: &str, passphrase: &str | {
+            let mut mixer = EntropyMixer::new();
+            mixer.add_text(salt).add_passphrase(passphrase, &params).unwrap();
+            *mixer.finalize().unwrap().expose_secret()
+        };
+        assert_eq!(derive("salt", "foo"), derive("salt", "foo"));
+        assert_ne!(derive("salt", "foo"), derive("salt", "bar"));
+        // the previous sources salt the derivation
+        assert_ne!(derive("salt", "foo"), derive("pepper", "foo"));
+        assert_ne!(derive("salt", "foo"), mix(&["salt", "foo"]));
+
+        let weak = KdfParams { memory_kib: 1, iterations: 1, parallelism: 1 };
+        assert!(EntropyMixer::new().add_passphrase("foo", &weak).is_err());
+    }
+}
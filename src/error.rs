@@ -0,0 +1,75 @@
+use kzg_ceremony_crypto::CeremonyError;
+use thiserror::Error;
+
+/**
+ * Errors returned by the wrapper functions
+ */
+#[derive(Debug, Error)]
+pub enum WrapperError {
+    #[error("ceremony error: {0}")]
+    Ceremony(#[from] CeremonyError),
+    #[error("ceremony error in sub-ceremony {index}: {source}")]
+    SubCeremony { index: usize, source: CeremonyError },
+    #[error("json (de)serialization failed: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("secret should be a 32 bytes hex string: {0}")]
+    Hex(#[from] hex::FromHexError),
+    #[error("identity deserialization failed: {0}")]
+    Identity(String),
+    #[error("invalid ceremony params: {0}")]
+    Params(String),
+    #[error("invalid engine: {0}")]
+    Engine(String),
+    #[error("invalid binary file: {0}")]
+    Binary(String),
+    #[error("invalid point: {0}")]
+    Point(String),
+    #[error("entropy collection failed: {0}")]
+    Entropy(String),
+    #[error("malformed transcript witness: {0}")]
+    Witness(String),
+    #[error("sequencer error: {0}")]
+    Sequencer(String),
+    #[error("invalid ECDSA signature: {0}")]
+    Signature(String),
+    #[error("receipt verification failed: {0}")]
+    Receipt(String),
+    #[error("cancelled")]
+    Cancelled,
+    #[error("i/o error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl WrapperError {
+    /// Stable identifier of the error kind, used by the wasm bindings
+    pub fn code(&self) -> &'static str {
+        match self {
+            WrapperError::Ceremony(_) => "CeremonyError",
+            WrapperError::SubCeremony { .. } => "CeremonyError",
+            WrapperError::Json(_) => "InvalidJson",
+            WrapperError::Hex(_) => "InvalidSecret",
+            WrapperError::Identity(_) => "InvalidIdentity",
+            WrapperError::Params(_) => "InvalidParams",
+            WrapperError::Engine(_) => "InvalidEngine",
+            WrapperError::Binary(_) => "InvalidBinary",
+            WrapperError::Point(_) => "InvalidPoint",
+            WrapperError::Entropy(_) => "EntropyError",
+            WrapperError::Witness(_) => "InvalidWitness",
+            WrapperError::Sequencer(_) => "SequencerError",
+            WrapperError::Signature(_) => "InvalidSignature",
+            WrapperError::Receipt(_) => "InvalidReceipt",
+            WrapperError::Cancelled => "Cancelled",
+            WrapperError::Io(_) => "IoError",
+        }
+    }
+
+    /// Index of the sub-ceremony the error refers to, if any
+    pub fn sub_ceremony(&self) -> Option<usize> {
+        match self {
+            WrapperError::SubCeremony { index, .. } => Some(*index),
+            _ => None,
+        }
+    }
+}
+
+pub type Result<T, E = WrapperError> = std::result::Result<T, E>;
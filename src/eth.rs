@@ -0,0 +1,87 @@
+use k256::ecdsa::{recoverable, VerifyingKey};
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use sha3::{Digest, Keccak256};
+use crate::{Result, WrapperError};
+
+/// Length of an `r | s | v` signature
+const SIGNATURE_SIZE: usize = 65;
+
+pub(crate) fn keccak256(data: &[u8]) -> [u8; 32] {
+    Keccak256::digest(data).into()
+}
+
+/// Digest of `personal_sign`, the way Ethereum accounts sign text
+pub(crate) fn eip191_digest(message: &[u8]) -> [u8; 32] {
+    let prefix = format!("\x19Ethereum Signed Message:\n{}", message.len());
+    keccak256(&[prefix.as_bytes(), message].concat())
+}
+
+/// Last 20 bytes of the keccak of the uncompressed public key
+pub(crate) fn address_of(key: &VerifyingKey) -> [u8; 20] {
+    let point = key.to_encoded_point(false);
+    let hash = keccak256(&point.as_bytes()[1..]);
+    hash[12..].try_into().unwrap()
+}
+
+pub(crate) fn address_to_string(address: &[u8; 20]) -> String {
+    format!("0x{}", hex::encode(address))
+}
+
+pub(crate) fn address_from_str(address: &str) -> Result<[u8; 20]> {
+    let bytes = address.strip_prefix("0x").map(hex::decode);
+    match bytes {
+        Some(Ok(bytes)) if bytes.len() == 20 => Ok(bytes.try_into().unwrap()),
+        _ => Err(WrapperError::Signature(format!("invalid Ethereum address {}", address))),
+    }
+}
+
+/// Address of the account that signed `digest`, from a hex `r | s | v` signature
+pub(crate) fn recover_address(digest: &[u8; 32], signature: &str) -> Result<[u8; 20]> {
+    let signature_error = || WrapperError::Signature(String::from("malformed ECDSA signature"));
+    let mut bytes = hex::decode(signature.trim_start_matches("0x")).map_err(|_| signature_error())?;
+    if bytes.len() != SIGNATURE_SIZE {
+        return Err(signature_error());
+    }
+    // wallets add 27 to the recovery id
+    if bytes[64] >= 27 {
+        bytes[64] -= 27;
+    }
+    let signature = recoverable::Signature::try_from(&bytes[..]).map_err(|_| signature_error())?;
+    let key = signature
+        .recover_verifying_key_from_digest_bytes(digest.into())
+        .map_err(|_| signature_error())?;
+    Ok(address_of(&key))
+}
+
+/// Hex `r | s | v` signature of `digest`, with `v` 27 or 28 like wallets
+#[cfg(any(test, feature = "mock-sequencer"))]
+pub(crate) fn sign_digest(key: &k256::ecdsa::SigningKey, digest: &[u8; 32]) -> String {
+    use k256::ecdsa::signature::hazmat::PrehashSigner;
+
+    let signature: recoverable::Signature = key.sign_prehash(digest).unwrap();
+    let mut bytes = signature.as_ref().to_vec();
+    bytes[64] += 27;
+    format!("0x{}", hex::encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k256::ecdsa::SigningKey;
+
+    #[test]
+    fn recover_address_test() {
+        // the first Hardhat account
+        let key = SigningKey::from_bytes(&hex::decode(
+            "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+        ).unwrap()).unwrap();
+        let address = address_of(&key.verifying_key());
+        assert_eq!(address_to_string(&address), "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266");
+
+        let digest = eip191_digest(b"hello");
+        let signature = sign_digest(&key, &digest);
+        assert_eq!(recover_address(&digest, &signature).unwrap(), address);
+        assert_ne!(recover_address(&eip191_digest(b"hullo"), &signature).unwrap(), address);
+        assert!(matches!(recover_address(&digest, "0x1234"), Err(WrapperError::Signature(_))));
+    }
+}
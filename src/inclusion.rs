@@ -0,0 +1,215 @@
+use serde::Serialize;
+use kzg_ceremony_crypto::{
+    G2,
+    Identity,
+    BatchTranscript,
+    Transcript,
+    CeremonyError,
+    Engine,
+};
+use crate::parallel::prelude::*;
+use crate::batch::first_failing_pubkey;
+use crate::{Result, WitnessCheck, WrapperError};
+
+/**
+ * Outcome of looking for a participant in a batch transcript
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum Inclusion {
+    /// The identity is not a participant of the transcript
+    NotFound,
+    /// One report per contribution of the identity
+    Found { reports: Vec<InclusionReport> },
+}
+
+/**
+ * In which sub-ceremonies a single contribution is included
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InclusionReport {
+    pub identity: String,
+    /// Index of the contribution in the witness of every transcript
+    pub witness_index: usize,
+    /// Sub-ceremonies where the witness chain verifies from the contribution
+    pub sub_ceremonies: Vec<usize>,
+    /// True when the contribution is included in every sub-ceremony
+    pub is_included: bool,
+}
+
+impl Inclusion {
+    /// True when the identity was found and every one of its contributions is included
+    pub fn is_included(&self) -> bool {
+        match self {
+            Inclusion::NotFound => false,
+            Inclusion::Found { reports } => reports.iter().all(|report| report.is_included),
+        }
+    }
+}
+
+/// Verifies that a contribution is included in the transcript
+pub fn verify_inclusion<E: Engine>(t: &Transcript, contrib_idx: usize, witness_check: WitnessCheck) -> Result<()> {
+    let products = &t.witness.products;
+    let pubkeys = &t.witness.pubkeys;
+    if products.len() != pubkeys.len() {
+        return Err(WrapperError::Witness(format!(
+            "{} running products but {} pubkeys", products.len(), pubkeys.len(),
+        )));
+    }
+    if contrib_idx >= products.len() {
+        return Err(WrapperError::Witness(format!(
+            "index {} is out of the {} witness entries", contrib_idx, products.len(),
+        )));
+    }
+
+    // Loop through subsequent witness entries. Do pairing check on each.
+
+    if pubkeys[contrib_idx..]
+        .par_iter()
+        .any(| pubkey | *pubkey == G2::zero())
+            { return Err(CeremonyError::ZeroPubkey.into()); };
+
+    // The genesis entry has no previous product, its successors are checked against it
+    let first_idx = contrib_idx.max(1);
+    // Pairing check: this & prev products, this pubkey
+    if first_failing_pubkey::<E>(products, pubkeys, first_idx..products.len(), witness_check).is_some() {
+        return Err(CeremonyError::PubKeyPairingFailed.into())
+    };
+
+    Ok(())
+}
+
+// Validate all transcripts for a given id
+pub fn verify_with_id<E:Engine>(bt: &BatchTranscript, id: &Identity, witness_check: WitnessCheck) -> Inclusion {
+    let witness_indexes: Vec<usize> = bt
+        .participant_ids
+        .iter()
+        .enumerate()
+        .filter(| (_, u) | *u == id)
+        .map(| (i, _) | i)
+        .collect();
+
+    if witness_indexes.is_empty() {
+        return Inclusion::NotFound;
+    }
+
+    let reports = witness_indexes
+        .into_iter()
+        .map(| witness_index | {
+            let sub_ceremonies: Vec<usize> = bt
+                .transcripts
+                .iter()
+                .enumerate()
+                .filter(| (_, t) | verify_inclusion::<E>(t, witness_index, witness_check).is_ok())
+                .map(| (i, _) | i)
+                .collect();
+            InclusionReport {
+                identity: id.to_string(),
+                witness_index,
+                is_included: sub_ceremonies.len() == bt.transcripts.len(),
+                sub_ceremonies,
+            }
+        })
+        .collect();
+
+    Inclusion::Found { reports }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kzg_ceremony_crypto::{DefaultEngine, Secret, G1};
+    use proptest::prelude::*;
+
+    /// Builds a valid witness chain with one tau per participant
+    fn synthetic_transcript(seeds: &[u8], num_sub_ceremonies: usize, ids: &[usize]) -> BatchTranscript {
+        let transcripts: Vec<_> = (0..num_sub_ceremonies)
+            .map(| sub_ceremony | {
+                let mut products = vec![G1::one()];
+                let mut pubkeys = vec![G2::one()];
+                for seed in seeds {
+                    let tau = DefaultEngine::generate_tau(&Secret::new([*seed ^ sub_ceremony as u8; 32]));
+                    let mut g1 = [G1::one(), *products.last().unwrap()];
+                    DefaultEngine::add_tau_g1(&tau, &mut g1).unwrap();
+                    let mut g2 = [G2::one(), G2::one()];
+                    DefaultEngine::add_tau_g2(&tau, &mut g2).unwrap();
+                    products.push(g1[1]);
+                    pubkeys.push(g2[1]);
+                }
+                serde_json::json!({
+                    "numG1Powers": 1,
+                    "numG2Powers": 1,
+                    "powersOfTau": {
+                        "G1Powers": [products.last().unwrap()],
+                        "G2Powers": [G2::one()],
+                    },
+                    "witness": {
+                        "runningProducts": products,
+                        "potPubkeys": pubkeys,
+                        "blsSignatures": vec![""; products.len()],
+                    },
+                })
+            })
+            .collect();
+
+        // genesis entry followed by the participants
+        let genesis_id = String::from("eth|0x0000000000000000000000000000000000000000");
+        let participant_ids: Vec<String> = std::iter::once(genesis_id)
+            .chain(ids.iter().map(| id | eth_id(*id).to_string()))
+            .collect();
+        let json = serde_json::json!({
+            "transcripts": transcripts,
+            "participantEcdsaSignatures": vec![""; participant_ids.len()],
+            "participantIds": participant_ids,
+        });
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn eth_id(id: usize) -> Identity {
+        Identity::eth_from_str(&format!("0x{:040x}", id + 1)).unwrap()
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn inclusion_is_total(
+            ids in prop::collection::vec(0usize..4, 1..6),
+            num_sub_ceremonies in 1usize..3,
+            queried in 0usize..6,
+            broken in prop::option::of(1usize..6),
+        ) {
+            let seeds: Vec<u8> = (1..=ids.len() as u8).collect();
+            let mut bt = synthetic_transcript(&seeds, num_sub_ceremonies, &ids);
+            // break the chain at a witness entry
+            let broken = broken.filter(| i | *i <= ids.len());
+            if let Some(i) = broken {
+                bt.transcripts[0].witness.pubkeys[i] = G2::one();
+            }
+
+            let id = eth_id(queried);
+            let inclusion = verify_with_id::<DefaultEngine>(&bt, &id, WitnessCheck::PerEntry);
+            // the batched check finds the same broken entries
+            prop_assert_eq!(&verify_with_id::<DefaultEngine>(&bt, &id, WitnessCheck::Batched), &inclusion);
+            match inclusion {
+                Inclusion::NotFound => prop_assert!(!ids.contains(&queried)),
+                Inclusion::Found { reports } => {
+                    let positions: Vec<usize> = reports.iter().map(| r | r.witness_index).collect();
+                    let expected: Vec<usize> = (1..=ids.len()).filter(| i | ids[i - 1] == queried).collect();
+                    prop_assert_eq!(positions, expected);
+                    for report in reports {
+                        // a contribution is included only if every later entry builds on it
+                        let is_broken = matches!(broken, Some(i) if i >= report.witness_index);
+                        prop_assert_eq!(report.is_included, !is_broken);
+                    }
+                },
+            }
+
+            // the genesis entry and out of range indexes never panic
+            let t = &bt.transcripts[0];
+            prop_assert_eq!(verify_inclusion::<DefaultEngine>(t, 0, WitnessCheck::Batched).is_ok(), broken.is_none());
+            prop_assert!(verify_inclusion::<DefaultEngine>(t, ids.len() + 1, WitnessCheck::PerEntry).is_err());
+        }
+    }
+}
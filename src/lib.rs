@@ -1,119 +1,506 @@
 #[cfg(target_family = "wasm")]
 mod wasm;
-
-use eyre::Result;
+mod entropy;
+mod batch;
+mod binary;
+mod cross_check;
+mod eip712;
+mod error;
+mod engine;
+mod eth;
+mod inclusion;
+#[cfg(all(any(test, feature = "mock-sequencer"), not(target_family = "wasm")))]
+mod mock_sequencer;
+mod parallel;
+mod params;
+mod points;
+mod progress;
+mod ptau;
+mod receipt;
+mod report;
+#[cfg(not(target_family = "wasm"))]
+mod sequencer;
+mod signatures;
+mod stream;
+mod trusted_setup;
+mod update;
+
+pub use batch::WitnessCheck;
+pub use binary::Batch;
+pub use cross_check::{CrossCheckReport, Divergence, cross_check_transcript};
+pub use eip712::{
+    EcdsaMismatch,
+    EcdsaSignaturesReport,
+    pot_pubkeys_digest,
+    pot_pubkeys_typed_data,
+    verify_ecdsa_signatures,
+    verify_pot_pubkeys_signature,
+};
+pub use entropy::{EntropyMixer, KdfParams, MouseSample};
+pub use engine::EngineKind;
+pub use error::{Result, WrapperError};
+pub use inclusion::{Inclusion, InclusionReport, verify_inclusion, verify_with_id};
+#[cfg(all(feature = "mock-sequencer", not(target_family = "wasm")))]
+pub use mock_sequencer::MockSequencer;
+pub use params::CeremonyParams;
+pub use progress::{CancelToken, NoProgress, Progress, ProgressUpdate, Stage};
+pub use ptau::{export_ptau, import_ptau};
+pub use receipt::{Receipt, verify_receipt};
+pub use report::{
+    CheckOutcome,
+    SubCeremonyReport,
+    Timings,
+    VerificationReport,
+    verify_contribution_report,
+    verify_transcript_report,
+    verify_transcript_report_with_progress,
+};
+#[cfg(not(target_family = "wasm"))]
+pub use sequencer::{
+    AuthLinks,
+    AuthProvider,
+    AuthSession,
+    ContributionReceipt,
+    IdToken,
+    SequencerClient,
+    SequencerConfig,
+    SequencerStatus,
+};
+pub use signatures::{BlsSignatureReport, SignatureStatus, verify_bls_signatures};
+pub use stream::{TranscriptParser, read_batch_contribution, read_batch_transcript};
+pub use trusted_setup::export_trusted_setup;
+pub use update::verify_update;
 use hex::FromHex;
+use engine::with_engine;
+use progress::Tracker;
 use std::str::FromStr;
 use std::{fs::File, path::Path};
 use ark_serialize::{Read, Write};
 //use serde::{Deserialize, Serialize};
 use kzg_ceremony_crypto::{
     G2,
-    BLST,
     Secret,
     Identity,
     get_pot_pubkeys,
     BatchContribution,
     BatchTranscript,
-    Transcript,
-    CeremonyError,
-    Engine,
+    EcdsaSignature,
 };
-use rayon::prelude::*;
 
 /**
  * We'll use this function in the cli
  */
-pub fn contribute_with_file(in_path: &str, out_path: &str, string_secret: &str, string_identity: &str) -> Result<()> {
+pub fn contribute_with_file(
+    in_path: &str,
+    out_path: &str,
+    string_secret: &str,
+    string_identity: &str,
+    params: &CeremonyParams,
+    engine: EngineKind,
+) -> Result<()> {
     let json = read_json_file(in_path)?;
-    let contribution = contribute_with_string(json, string_secret, string_identity)?;
+    let contribution = contribute_with_string(json, string_secret, string_identity, params, engine)?;
 
     write_json_file(out_path, &contribution)
 }
 /**
  * We'll use this function in the wasm
  */
-pub fn contribute_with_string(json: String, string_secret: &str, string_identity: &str) -> Result<String> {
+pub fn contribute_with_string(
+    json: String,
+    string_secret: &str,
+    string_identity: &str,
+    params: &CeremonyParams,
+    engine: EngineKind,
+) -> Result<String> {
+    contribute_with_progress(json, string_secret, string_identity, params, engine, &NoProgress, &CancelToken::new())
+}
+/**
+ * We'll use this function in the cli/wasm, to show a progress bar and let the user abort.
+ *
+ * This is not chunked progress, it is a limitation of kzg-ceremony-crypto: its
+ * `BatchContribution::add_entropy` adds the secret to every sub-ceremony in one call,
+ * and derives the secret of each sub-ceremony internally, so it can't be split per
+ * sub-ceremony or per chunk of points. The progress jumps from 0% before the call to
+ * 100% after it, and the token is only checked before the call starts and after it ends
+ */
+pub fn contribute_with_progress(
+    json: String,
+    string_secret: &str,
+    string_identity: &str,
+    params: &CeremonyParams,
+    engine: EngineKind,
+    progress: &dyn Progress,
+    cancel: &CancelToken,
+) -> Result<String> {
     // parse contribution object
-    let mut contribution = serde_json::from_str::<BatchContribution>(&json)
-    .expect("Contribution deserialization failed");
+    let mut contribution = serde_json::from_str::<BatchContribution>(&json)?;
     // parse entropy
-    let entropy = string_to_entropy(string_secret);
+    let entropy = string_to_entropy(string_secret)?;
     // parse identity (eth or git)
-    let identity = Identity::from_str(string_identity)
-    .expect("Identity deserialization failed");
+    let identity = string_to_identity(string_identity)?;
+    // check the shape of the ceremony
+    params.check_contribution(&contribution)?;
 
-    contribution.add_entropy::<BLST>(&entropy, &identity)
-    .expect("Contribution computation failed");
-    let result = serde_json::to_string(&contribution)
-    .expect("Result Contribution serialization failed");
+    let num_points = contribution
+        .contributions
+        .iter()
+        .map(| contribution | contribution.powers.g1.len() + contribution.powers.g2.len())
+        .sum();
+    let num_sub_ceremonies = contribution.contributions.len();
+    let tracker = Tracker::new(progress, cancel, num_sub_ceremonies, num_points);
+    tracker.advance(Stage::Contribute, 0, 0)?;
+    with_engine!(engine, E => contribution.add_entropy::<E>(&entropy, &identity))?;
+    tracker.advance(Stage::Contribute, num_sub_ceremonies.saturating_sub(1), num_points)?;
+    let result = serde_json::to_string(&contribution)?;
 
     Ok(result)
 }
 
 
+/**
+ * We'll use this function in the wasm
+ */
+pub fn verify_update_with_string(previous_json: String, updated_json: String, engine: EngineKind) -> Result<()> {
+    // parse contribution objects
+    let previous = serde_json::from_str::<BatchContribution>(&previous_json)?;
+    let updated = serde_json::from_str::<BatchContribution>(&updated_json)?;
+
+    with_engine!(engine, E => verify_update::<E>(&previous, &updated))
+}
+
+
 /**
  * We'll use this function in the cli
  */
-pub fn check_subgroup_with_file(in_path: &str) -> Result<()> {
-    let json = read_json_file(in_path)?;
-    let result = check_subgroup_with_string(json)?;
-    Ok(println!("Subgroup check is correct: {:?}", result))
+pub fn check_subgroup_with_file(in_path: &str, params: &CeremonyParams, engine: EngineKind) -> Result<()> {
+    let file = File::open(Path::new(in_path))?;
+    let contribution = read_batch_contribution(file)?;
+    check_subgroup(contribution, params, engine)
 }
 /**
  * We'll use this function in the wasm
  */
-pub fn check_subgroup_with_string(json: String) -> Result<bool> {
+pub fn check_subgroup_with_string(json: String, params: &CeremonyParams, engine: EngineKind) -> Result<()> {
     // parse contribution object
-    let mut contribution = serde_json::from_str::<BatchContribution>(&json)
-    .expect("Contribution deserialization failed");
+    let contribution = serde_json::from_str::<BatchContribution>(&json)?;
+    check_subgroup(contribution, params, engine)
+}
 
-    let result = contribution.validate::<BLST>();
+/**
+ * We'll use this function in the wasm
+ */
+pub fn check_subgroup_report_with_string(json: String, params: &CeremonyParams, engine: EngineKind) -> Result<VerificationReport> {
+    // parse contribution object
+    let contribution = serde_json::from_str::<BatchContribution>(&json)?;
+    Ok(with_engine!(engine, E => verify_contribution_report::<E>(&contribution, params)))
+}
+
+/// Fails with the reason the contribution is invalid, `check_subgroup_report_with_string` checks everything
+fn check_subgroup(mut contribution: BatchContribution, params: &CeremonyParams, engine: EngineKind) -> Result<()> {
+    params.check_contribution(&contribution)?;
 
-    let is_valid = match result {
-        Ok(()) => true,
-        Err(error) => {
-            println!("{:?}", error);
-            false
-        },
-    };
-    Ok(is_valid)
+    with_engine!(engine, E => contribution.validate::<E>())?;
+    Ok(())
 }
 /**
  * We'll use this function in the wasm
  */
-pub fn get_pot_pubkeys_with_string(string_secret: &str) -> Result<Vec<G2>> {
-    let entropy = string_to_entropy(string_secret);
-    let pot_pubkeys = get_pot_pubkeys::<BLST>(&entropy);
+pub fn get_pot_pubkeys_with_string(string_secret: &str, engine: EngineKind) -> Result<Vec<G2>> {
+    let entropy = string_to_entropy(string_secret)?;
+    let pot_pubkeys = with_engine!(engine, E => get_pot_pubkeys::<E>(&entropy));
     Ok(pot_pubkeys)
 }
 /**
  * We'll use this function in the cli
  */
-pub fn verify_with_file(in_path: &str) -> Result<()> {
-    let json = read_json_file(in_path)?;
-    let result = verify_with_string(json)?;
-    Ok(println!("Verification is correct: {:?}", result))
+pub fn verify_with_file(in_path: &str, params: &CeremonyParams, engine: EngineKind) -> Result<()> {
+    let file = File::open(Path::new(in_path))?;
+    verify_with_reader(file, params, engine)
 }
 /**
  * We'll use this function in the wasm
  */
-pub fn verify_with_string(json: String) -> Result<bool> {
+pub fn verify_with_string(json: String, params: &CeremonyParams, engine: EngineKind) -> Result<()> {
     // parse batch transcript object
-    let batch_transcript = serde_json::from_str::<BatchTranscript>(&json)
-    .expect("BatchTranscript deserialization failed");
+    let batch_transcript = serde_json::from_str::<BatchTranscript>(&json)?;
+    verify_batch_transcript(&batch_transcript, params, engine)
+}
+/**
+ * We'll use this function for the full transcript, streamed from a file or stdin
+ */
+pub fn verify_with_reader<R: Read>(reader: R, params: &CeremonyParams, engine: EngineKind) -> Result<()> {
+    let batch_transcript = read_batch_transcript(reader)?;
+    verify_batch_transcript(&batch_transcript, params, engine)
+}
 
-    let sizes = vec![(4096, 65)];
-    let result = batch_transcript.verify_self::<BLST>(sizes);
+/**
+ * We'll use this function in the wasm
+ */
+pub fn verify_report_with_string(
+    json: String,
+    params: &CeremonyParams,
+    engine: EngineKind,
+    witness_check: WitnessCheck,
+) -> Result<VerificationReport> {
+    // parse batch transcript object
+    let batch_transcript = serde_json::from_str::<BatchTranscript>(&json)?;
+    Ok(report_batch_transcript(&batch_transcript, params, engine, witness_check))
+}
+/**
+ * We'll use this function for the full transcript, streamed from a file or stdin
+ */
+pub fn verify_report_with_reader<R: Read>(
+    reader: R,
+    params: &CeremonyParams,
+    engine: EngineKind,
+    witness_check: WitnessCheck,
+) -> Result<VerificationReport> {
+    let batch_transcript = read_batch_transcript(reader)?;
+    Ok(report_batch_transcript(&batch_transcript, params, engine, witness_check))
+}
+/**
+ * We'll use this function in the cli, to run more checks on the same transcript
+ */
+pub fn report_batch_transcript(
+    batch_transcript: &BatchTranscript,
+    params: &CeremonyParams,
+    engine: EngineKind,
+    witness_check: WitnessCheck,
+) -> VerificationReport {
+    with_engine!(engine, E => verify_transcript_report::<E>(batch_transcript, params, witness_check))
+}
 
-    let is_valid = match result {
-        Ok(()) => true,
-        Err(error) => {
-            println!("{:?}", error);
-            false
-        },
-    };
-    Ok(is_valid)
+/**
+ * We'll use this function in the cli/wasm, to show a progress bar and let the user abort
+ */
+pub fn verify_report_with_progress<R: Read>(
+    reader: R,
+    params: &CeremonyParams,
+    engine: EngineKind,
+    witness_check: WitnessCheck,
+    progress: &dyn Progress,
+    cancel: &CancelToken,
+) -> Result<VerificationReport> {
+    let batch_transcript = read_batch_transcript(reader)?;
+    report_batch_transcript_with_progress(&batch_transcript, params, engine, witness_check, progress, cancel)
+}
+/**
+ * We'll use this function in the cli, to run more checks on the same transcript
+ */
+pub fn report_batch_transcript_with_progress(
+    batch_transcript: &BatchTranscript,
+    params: &CeremonyParams,
+    engine: EngineKind,
+    witness_check: WitnessCheck,
+    progress: &dyn Progress,
+    cancel: &CancelToken,
+) -> Result<VerificationReport> {
+    with_engine!(engine, E => verify_transcript_report_with_progress::<E>(batch_transcript, params, witness_check, progress, cancel))
+}
+
+/**
+ * We'll use this function in the wasm
+ */
+pub fn cross_check_with_string(json: String, params: &CeremonyParams) -> Result<CrossCheckReport> {
+    // parse batch transcript object
+    let batch_transcript = serde_json::from_str::<BatchTranscript>(&json)?;
+    cross_check_transcript(&batch_transcript, params)
+}
+/**
+ * We'll use this function for the full transcript, streamed from a file or stdin
+ */
+pub fn cross_check_with_reader<R: Read>(reader: R, params: &CeremonyParams) -> Result<CrossCheckReport> {
+    let batch_transcript = read_batch_transcript(reader)?;
+    cross_check_transcript(&batch_transcript, params)
+}
+
+/**
+ * We'll use this function in the cli, to run more checks on the same transcript.
+ * Fails with the first reason the transcript is invalid, `report_batch_transcript` lists them all
+ */
+pub fn verify_batch_transcript(batch_transcript: &BatchTranscript, params: &CeremonyParams, engine: EngineKind) -> Result<()> {
+    let sizes = params.sizes().to_vec();
+    with_engine!(engine, E => batch_transcript.verify_self::<E>(sizes))?;
+    Ok(())
+}
+/**
+ * We'll use this function in the cli
+ */
+pub fn verify_inclusion_with_file(
+    in_path: &str,
+    string_identity: &str,
+    engine: EngineKind,
+    witness_check: WitnessCheck,
+) -> Result<Inclusion> {
+    let file = File::open(Path::new(in_path))?;
+    verify_inclusion_with_reader(file, string_identity, engine, witness_check)
+}
+/**
+ * We'll use this function in the wasm
+ */
+pub fn verify_inclusion_with_string(
+    json: String,
+    string_identity: &str,
+    engine: EngineKind,
+    witness_check: WitnessCheck,
+) -> Result<Inclusion> {
+    // parse batch transcript object
+    let batch_transcript = serde_json::from_str::<BatchTranscript>(&json)?;
+    let identity = string_to_identity(string_identity)?;
+
+    let inclusion = with_engine!(engine, E => verify_with_id::<E>(&batch_transcript, &identity, witness_check));
+    Ok(inclusion)
+}
+/**
+ * We'll use this function for the full transcript, streamed from a file or stdin
+ */
+pub fn verify_inclusion_with_reader<R: Read>(
+    reader: R,
+    string_identity: &str,
+    engine: EngineKind,
+    witness_check: WitnessCheck,
+) -> Result<Inclusion> {
+    // parse the identity first, no need to read the transcript if it is invalid
+    let identity = string_to_identity(string_identity)?;
+    let batch_transcript = read_batch_transcript(reader)?;
+
+    let inclusion = with_engine!(engine, E => verify_with_id::<E>(&batch_transcript, &identity, witness_check));
+    Ok(inclusion)
+}
+/**
+ * We'll use this function in the wasm
+ */
+pub fn verify_bls_signatures_with_string(json: String, engine: EngineKind) -> Result<Vec<BlsSignatureReport>> {
+    // parse batch transcript object
+    let batch_transcript = serde_json::from_str::<BatchTranscript>(&json)?;
+    with_engine!(engine, E => verify_bls_signatures::<E>(&batch_transcript))
+}
+/**
+ * We'll use this function for the full transcript, streamed from a file or stdin
+ */
+pub fn verify_bls_signatures_with_reader<R: Read>(reader: R, engine: EngineKind) -> Result<Vec<BlsSignatureReport>> {
+    let batch_transcript = read_batch_transcript(reader)?;
+    with_engine!(engine, E => verify_bls_signatures::<E>(&batch_transcript))
+}
+
+/**
+ * We'll use this function in the wasm
+ */
+pub fn verify_ecdsa_signatures_with_string(json: String) -> Result<EcdsaSignaturesReport> {
+    // parse batch transcript object
+    let batch_transcript = serde_json::from_str::<BatchTranscript>(&json)?;
+    verify_ecdsa_signatures(&batch_transcript)
+}
+/**
+ * We'll use this function for the full transcript, streamed from a file or stdin
+ */
+pub fn verify_ecdsa_signatures_with_reader<R: Read>(reader: R) -> Result<EcdsaSignaturesReport> {
+    let batch_transcript = read_batch_transcript(reader)?;
+    verify_ecdsa_signatures(&batch_transcript)
+}
+
+
+/**
+ * We'll use this function in the cli/wasm
+ */
+pub fn verify_receipt_with_string(
+    receipt: &str,
+    signature: &str,
+    sequencer_address: &str,
+    string_secret: &str,
+    string_identity: &str,
+    params: &CeremonyParams,
+    engine: EngineKind,
+) -> Result<Receipt> {
+    let identity = string_to_identity(string_identity)?;
+    // the pubkeys the receipt should hold are derived from the participant secret
+    let pot_pubkeys = string_to_pot_pubkeys(string_secret, params, engine)?;
+    verify_receipt(receipt, signature, sequencer_address, &identity, &pot_pubkeys)
+}
+/**
+ * We'll use this function in the cli/wasm
+ */
+pub fn pot_pubkeys_typed_data_with_string(string_secret: &str, params: &CeremonyParams, engine: EngineKind) -> Result<String> {
+    let pot_pubkeys = string_to_pot_pubkeys(string_secret, params, engine)?;
+    let typed_data = pot_pubkeys_typed_data(params.sizes(), &pot_pubkeys);
+    Ok(typed_data.to_string())
+}
+/**
+ * We'll use this function in the cli/wasm
+ */
+pub fn add_ecdsa_signature_with_string(json: String, signature: &str, string_identity: &str) -> Result<String> {
+    // parse contribution object
+    let mut contribution = serde_json::from_str::<BatchContribution>(&json)?;
+    let identity = string_to_identity(string_identity)?;
+    // a wrong signature would be dropped by the sequencer, better fail now
+    if !verify_contribution_signature(&contribution, signature, &identity)? {
+        return Err(WrapperError::Signature(format!("the potPubkeys were not signed by {}", identity)));
+    }
+
+    contribution.ecdsa_signature = serde_json::from_value::<EcdsaSignature>(signature.into())?;
+    let result = serde_json::to_string(&contribution)?;
+    Ok(result)
+}
+/**
+ * We'll use this function in the cli/wasm
+ */
+pub fn verify_ecdsa_signature_with_string(json: String, string_identity: &str) -> Result<bool> {
+    let contribution = serde_json::from_str::<BatchContribution>(&json)?;
+    let identity = string_to_identity(string_identity)?;
+    let signature = serde_json::to_value(&contribution.ecdsa_signature)?;
+    match signature.as_str() {
+        Some(signature) if !signature.is_empty() => verify_contribution_signature(&contribution, signature, &identity),
+        _ => Ok(false),
+    }
+}
+
+fn verify_contribution_signature(contribution: &BatchContribution, signature: &str, identity: &Identity) -> Result<bool> {
+    let sizes: Vec<_> = contribution
+        .contributions
+        .iter()
+        .map(| contribution | (contribution.powers.g1.len(), contribution.powers.g2.len()))
+        .collect();
+    verify_pot_pubkeys_signature(&sizes, &contribution.receipt(), signature, identity)
+}
+
+
+/**
+ * We'll use this function in the cli
+ */
+pub fn export_trusted_setup_with_reader<R: Read>(
+    reader: R,
+    sub_ceremony_index: usize,
+    params: &CeremonyParams,
+    engine: EngineKind,
+) -> Result<String> {
+    let batch_transcript = read_batch_transcript(reader)?;
+    // only a verified transcript can be deployed
+    let sizes = params.sizes().to_vec();
+    with_engine!(engine, E => batch_transcript.verify_self::<E>(sizes))?;
+
+    export_trusted_setup(&batch_transcript, sub_ceremony_index)
+}
+/**
+ * We'll use this function in the cli
+ */
+pub fn export_ptau_with_reader<R: Read, W: Write>(
+    reader: R,
+    writer: W,
+    sub_ceremony_index: usize,
+    params: &CeremonyParams,
+    engine: EngineKind,
+) -> Result<()> {
+    let batch_transcript = read_batch_transcript(reader)?;
+    // only a verified transcript can be deployed
+    let sizes = params.sizes().to_vec();
+    with_engine!(engine, E => batch_transcript.verify_self::<E>(sizes))?;
+
+    let transcript = batch_transcript
+        .transcripts
+        .get(sub_ceremony_index)
+        .ok_or_else(|| WrapperError::Params(format!("no sub-ceremony {}", sub_ceremony_index)))?;
+    export_ptau(transcript, writer)
 }
 
 
@@ -122,104 +509,112 @@ pub fn verify_with_string(json: String) -> Result<bool> {
  */
 fn read_json_file(string_path: &str) -> Result<String> {
     let path = Path::new(string_path);
-    let mut file = File::open(path)
-    .expect("error opening file");
+    let mut file = File::open(path)?;
     let mut content = String::new();
-    file.read_to_string(&mut content)
-    .expect("error reading file");
+    file.read_to_string(&mut content)?;
     Ok(content)
 }
 
 fn write_json_file(string_path: &str, content: &str) -> Result<()> {
     let buf = content.as_bytes();
     let path = Path::new(string_path);
-    let mut file = File::create(path).expect("error creating file");
-    file.write_all(buf).expect("error writing in file");
+    let mut file = File::create(path)?;
+    file.write_all(buf)?;
     Ok(())
 }
 
-fn string_to_entropy(string_secret: &str) -> Secret<[u8; 32]> {
-    let buffer = <[u8; 32]>::from_hex(string_secret)
-    .expect("secret should be a 32 hex string");
+fn string_to_entropy(string_secret: &str) -> Result<Secret<[u8; 32]>> {
+    let buffer = <[u8; 32]>::from_hex(string_secret)?;
     let entropy = Secret::from(buffer);
-   entropy
-}
-
-/// Verifies that a contribution is included in the transcript
-fn verify_inclusion<E: Engine>(t: &Transcript, contrib_idx: usize) -> Result<(), CeremonyError> {
-    assert!(contrib_idx < t.witness.products.len());
-
-    // Loop through subsequent witness entries. Do pairing check on each.
-
-    if t
-        .witness
-        .pubkeys
-        .par_iter()
-        .enumerate()
-        .filter(| (i, _) | i>=&contrib_idx)
-        .map(| (_, p) | p)
-        .any(| pubkey | *pubkey == G2::zero())
-            { return Err(CeremonyError::ZeroPubkey); };
-
-    if t
-        .witness
-        .products
-        .par_iter()
-        .enumerate()
-        .filter(| (i, _) | i>=&contrib_idx)
-        .any(|(i, product)|
-            // Pairing check: this & prev products, this pubkey
-            E::verify_pubkey(
-                *product,
-                t.witness.products[i - 1],
-                t.witness.pubkeys[i],
-            ).is_err())
-            {
-                return Err(CeremonyError::PubKeyPairingFailed)
-            };
-
-    Ok(())
+    Ok(entropy)
 }
 
-// Validate all transcripts for a given id
-fn verify_with_id<E:Engine>(bt: &BatchTranscript, id: Identity) -> Result<(), CeremonyError> {
-    let index = bt
-        .participant_ids
-        .par_iter()
-        .position(| u | *u == id)
-        .unwrap();
-
-    if bt
-        .transcripts
-        .iter()
-        .any( | t | verify_inclusion::<E>(t, index).is_err()) {
-            return Err(CeremonyError::PubKeyPairingFailed);
-        };
+/// potPubkeys of the secret, one per sub-ceremony
+fn string_to_pot_pubkeys(string_secret: &str, params: &CeremonyParams, engine: EngineKind) -> Result<Vec<G2>> {
+    let mut pot_pubkeys = get_pot_pubkeys_with_string(string_secret, engine)?;
+    pot_pubkeys.truncate(params.sizes().len());
+    Ok(pot_pubkeys)
+}
 
-    Ok(())
+fn string_to_identity(string_identity: &str) -> Result<Identity> {
+    Identity::from_str(string_identity)
+    .map_err(|error| WrapperError::Identity(error.to_string()))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use kzg_ceremony_crypto::DefaultEngine;
+    use kzg_ceremony_crypto::{DefaultEngine, CeremonyError, Transcript};
 
     #[test]
     fn secrets_to_pubkey_test() {
         // This test ensures that pubkeys dericvation appears correct
         let string_secret = "6b86b273ff34fce19d6b804eff5a3f5747ada4eaa22f1d49c01e52ddb7875b4b";
 
-        let pot_pubkeys = get_pot_pubkeys_with_string(string_secret).unwrap();
-        println!("{:?}", serde_json::to_value(&pot_pubkeys[0]).unwrap());
-        println!("{:?}", serde_json::to_value(&pot_pubkeys[1]).unwrap());
-        println!("{:?}", serde_json::to_value(&pot_pubkeys[2]).unwrap());
-        println!("{:?}", serde_json::to_value(&pot_pubkeys[3]).unwrap());
+        let pot_pubkeys = get_pot_pubkeys_with_string(string_secret, EngineKind::default()).unwrap();
+        println!("{:?}", serde_json::to_value(pot_pubkeys[0]).unwrap());
+        println!("{:?}", serde_json::to_value(pot_pubkeys[1]).unwrap());
+        println!("{:?}", serde_json::to_value(pot_pubkeys[2]).unwrap());
+        println!("{:?}", serde_json::to_value(pot_pubkeys[3]).unwrap());
 
         let value_pot_pubkeys_0 = serde_json::to_string(&pot_pubkeys[0]).unwrap();
         assert_eq!(value_pot_pubkeys_0.get(0..3).unwrap(), String::from("\"0x"));
         assert_eq!(value_pot_pubkeys_0.len(), 194 + 2);
     }
 
+    #[test]
+    fn invalid_inputs_test() {
+        // This test ensures that bad inputs are reported as errors instead of panics
+        let string_secret = "6b86b273ff34fce19d6b804eff5a3f5747ada4eaa22f1d49c01e52ddb7875b4b";
+        let string_identity = "eth|0x000000000000000000000000000000000000dead";
+
+        let result = get_pot_pubkeys_with_string("not an hex secret", EngineKind::default());
+        assert!(matches!(result, Err(WrapperError::Hex(_))));
+
+        let params = CeremonyParams::ethereum();
+
+        let result = contribute_with_string(String::from("{"), string_secret, string_identity, &params, EngineKind::default());
+        assert!(matches!(result, Err(WrapperError::Json(_))));
+
+        let json = serde_json::json!({ "contributions": [] }).to_string();
+        let result = contribute_with_string(json.clone(), string_secret, "foo|bar", &params, EngineKind::default());
+        assert!(matches!(result, Err(WrapperError::Identity(_))));
+
+        let result = contribute_with_string(json, string_secret, string_identity, &params, EngineKind::default());
+        assert!(matches!(result, Err(WrapperError::Ceremony(CeremonyError::UnexpectedNumContributions(4, 0)))));
+
+        let result = verify_with_file("does/not/exist.json", &params, EngineKind::default());
+        assert!(matches!(result, Err(WrapperError::Io(_))));
+
+        // a failed check is returned, not printed
+        let transcript = serde_json::to_string(&BatchTranscript::new(&[(4, 2), (8, 3)])).unwrap();
+        let result = verify_with_string(transcript, &params, EngineKind::default());
+        assert!(matches!(result, Err(WrapperError::Ceremony(_))));
+    }
+
+    #[test]
+    fn contribute_with_progress_test() {
+        let string_secret = "6b86b273ff34fce19d6b804eff5a3f5747ada4eaa22f1d49c01e52ddb7875b4b";
+        let string_identity = "git|1|alice";
+        let params = CeremonyParams::new(vec![(4, 2), (8, 3)]).unwrap();
+        let json = serde_json::to_string(&BatchTranscript::new(params.sizes()).contribution()).unwrap();
+
+        // the same contribution, reported before and after computing it
+        let updates = std::cell::RefCell::new(Vec::new());
+        let progress = | update: &ProgressUpdate | updates.borrow_mut().push(update.clone());
+        let result = contribute_with_progress(json.clone(), string_secret, string_identity, &params, EngineKind::default(), &progress, &CancelToken::new());
+        let expected = contribute_with_string(json.clone(), string_secret, string_identity, &params, EngineKind::default()).unwrap();
+        assert_eq!(result.unwrap(), expected);
+        let updates = updates.into_inner();
+        assert_eq!(updates.iter().map(| update | (update.points_processed, update.percentage)).collect::<Vec<_>>(), vec![(0, 0.0), (17, 100.0)]);
+
+        // cancelled from the callback, the contribution is not computed
+        let cancel = CancelToken::new();
+        let progress = | _: &ProgressUpdate | cancel.cancel();
+        let result = contribute_with_progress(json, string_secret, string_identity, &params, EngineKind::default(), &progress, &cancel);
+        assert!(matches!(result, Err(WrapperError::Cancelled)));
+    }
+
     #[test]
     fn test_verify_inclusion() {
         let json = serde_json::json!({
@@ -262,13 +657,13 @@ mod tests {
         let mut t = serde_json::from_value::<Transcript>(json).unwrap();
 
         // Verify pubkey sequence to end
-        let result = verify_inclusion::<DefaultEngine>(&t, 1);
-        assert_eq!(Ok(()), result);
+        let result = verify_inclusion::<DefaultEngine>(&t, 1, WitnessCheck::PerEntry);
+        assert!(result.is_ok());
 
         // Make it fail with a 0 point
         t.witness.pubkeys[5] = G2::zero();
-        let result2 = verify_inclusion::<DefaultEngine>(&t, 1);
-        assert_eq!(Err(CeremonyError::ZeroPubkey), result2);
+        let result2 = verify_inclusion::<DefaultEngine>(&t, 1, WitnessCheck::PerEntry);
+        assert!(matches!(result2, Err(WrapperError::Ceremony(CeremonyError::ZeroPubkey))));
 
     }
 
@@ -488,9 +883,47 @@ mod tests {
         let bt = serde_json::from_value::<BatchTranscript>(json).unwrap();
 
         // Verify pubkey sequence to end
-        let result = verify_with_id::<DefaultEngine>(&bt, u_id);
-        assert!(result.is_ok());
+        let result = verify_with_id::<DefaultEngine>(&bt, &u_id, WitnessCheck::PerEntry);
+        assert!(result.is_included());
 
+        // Report where the contribution is included
+        let reports = match result {
+            Inclusion::Found { reports } => reports,
+            Inclusion::NotFound => panic!("participant not found"),
+        };
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].witness_index, 1);
+        assert_eq!(reports[0].sub_ceremonies, vec![0, 1, 2, 3]);
+
+        let unknown_id = Identity::eth_from_str("0x000000000000000000000000000000000000dead").unwrap();
+        assert_eq!(verify_with_id::<DefaultEngine>(&bt, &unknown_id, WitnessCheck::PerEntry), Inclusion::NotFound);
+    }
+
+    #[test]
+    fn ecdsa_signature_test() {
+        use k256::ecdsa::SigningKey;
+        use crate::eth::{address_of, address_to_string, sign_digest};
+
+        let params = CeremonyParams::new(vec![(4, 2), (8, 3)]).unwrap();
+        let secret = "6b86b273ff34fce19d6b804eff5a3f5747ada4eaa22f1d49c01e52ddb7875b4b";
+        let key = SigningKey::from_bytes(&[7; 32]).unwrap();
+        let identity = format!("eth|{}", address_to_string(&address_of(&key.verifying_key())));
+
+        // the wallet signs the typed data of the pubkeys before the contribution
+        let typed_data = pot_pubkeys_typed_data_with_string(secret, &params, EngineKind::default()).unwrap();
+        assert!(typed_data.contains("\"primaryType\":\"PoTPubkeys\""));
+        let pot_pubkeys = string_to_pot_pubkeys(secret, &params, EngineKind::default()).unwrap();
+        let signature = sign_digest(&key, &pot_pubkeys_digest(params.sizes(), &pot_pubkeys));
+
+        let initial = serde_json::to_string(&BatchTranscript::new(params.sizes()).contribution()).unwrap();
+        let updated = contribute_with_string(initial, secret, &identity, &params, EngineKind::default()).unwrap();
+        assert!(!verify_ecdsa_signature_with_string(updated.clone(), &identity).unwrap());
+        let signed = add_ecdsa_signature_with_string(updated.clone(), &signature, &identity).unwrap();
+        assert!(verify_ecdsa_signature_with_string(signed, &identity).unwrap());
+
+        let other_identity = "eth|0x000000000000000000000000000000000000dead";
+        let result = add_ecdsa_signature_with_string(updated, &signature, other_identity);
+        assert!(matches!(result, Err(WrapperError::Signature(_))));
     }
 
 }
\ No newline at end of file
@@ -1,45 +1,617 @@
-use std::time::Instant;
+use std::fmt::Display;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+use serde::Serialize;
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
 use wrapper_small_pot::{
-    contribute_with_file,
-    check_subgroup_with_file,
+    Batch,
+    BlsSignatureReport,
+    CeremonyParams,
+    ContributionReceipt,
+    EngineKind,
+    EntropyMixer,
+    KdfParams,
+    NoProgress,
+    Progress,
+    ProgressUpdate,
+    Result,
+    SequencerClient,
+    SequencerConfig,
+    WitnessCheck,
+    WrapperError,
+    CancelToken,
+    contribute_with_progress,
+    add_ecdsa_signature_with_string,
+    pot_pubkeys_typed_data_with_string,
+    verify_update_with_string,
+    check_subgroup_report_with_string,
+    cross_check_transcript,
     get_pot_pubkeys_with_string,
-    verify_with_file,
+    read_batch_transcript,
+    report_batch_transcript_with_progress,
+    verify_ecdsa_signatures,
+    verify_inclusion_with_reader,
+    verify_bls_signatures_with_reader,
+    verify_receipt_with_string,
+    export_trusted_setup_with_reader,
+    export_ptau_with_reader,
 };
 
-fn main() {
-    println!("Hello, wrapper-small-pot!");
-
-    let in_path = "wasm/initialContribution.json";
-    let out_path = "wasm/updatedContribution.json";
-    let string_identity = "eth|0x000000000000000000000000000000000000dead";
-    let string_secret = "6b86b273ff34fce19d6b804eff5a3f5747ada4eaa22f1d49c01e52ddb7875b4b";
-
-    println!("get potPubkeys from secret initialized");
-    let start_get_pot_pubkeys = Instant::now();
-    let pot_pubkeys = get_pot_pubkeys_with_string(string_secret).unwrap();
-    println!("{:?}", serde_json::to_value(pot_pubkeys).unwrap());
-    println!("get potPubkeys time: {:?}", start_get_pot_pubkeys.elapsed());
-
-    println!("contribute with file initialized");
-    let start_contribute = Instant::now();
-    contribute_with_file(
-        in_path,
-        out_path,
-        string_secret,
-        string_identity,
-    ).unwrap();
-    println!("contribute time: {:?}", start_contribute.elapsed());
-
-
-    println!("subgroup check with file initialized");
-    let start_subgroup_check = Instant::now();
-    check_subgroup_with_file(in_path).unwrap();
-    println!("subgroup check time: {:?}", start_subgroup_check.elapsed());
-
-    let transcript_path = "wasm/transcript.json";
-    println!("verify with file initialized");
-    let start_verify = Instant::now();
-    verify_with_file(transcript_path).unwrap();
-    println!("verify time: {:?}", start_verify.elapsed());
-
-}
\ No newline at end of file
+/// Process exit codes
+const EXIT_CHECK_FAILED: u8 = 1;
+const EXIT_INVALID_INPUT: u8 = 3;
+const EXIT_IO_ERROR: u8 = 4;
+const EXIT_CEREMONY_ERROR: u8 = 5;
+const EXIT_SEQUENCER_ERROR: u8 = 6;
+const EXIT_CANCELLED: u8 = 130;
+
+/// Small Powers of Tau wrapper for offline contributions and transcript audits
+#[derive(Parser)]
+#[clap(version, about)]
+struct Cli {
+    #[clap(subcommand)]
+    command: Command,
+    /// BLS12-381 backend: "blst" or "arkworks", among the ones this binary was built with
+    #[clap(long, global = true, value_parser, default_value_t)]
+    engine: EngineKind,
+    /// Print the time taken by the subcommand to stderr
+    #[clap(long, global = true)]
+    verbose: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Add your entropy to a batch contribution and check the result
+    Contribute {
+        #[clap(flatten)]
+        input: InputArgs,
+        /// Path of the updated contribution, or "-" for stdout
+        #[clap(short, long, default_value = "-")]
+        output: PathBuf,
+        #[clap(flatten)]
+        secret: SecretArgs,
+        /// Participant identity, like "eth|0x..." or "git|<id>|<username>"
+        #[clap(long)]
+        identity: String,
+        #[clap(flatten)]
+        params: ParamsArgs,
+        /// Wallet signature of the `pubkeys --typed-data` output, added to the contribution
+        #[clap(long, value_name = "HEX")]
+        ecdsa_signature: Option<String>,
+        /// Print the progress to stderr
+        #[clap(long)]
+        progress: bool,
+    },
+    /// Print the potPubkeys derived from a secret
+    Pubkeys {
+        #[clap(flatten)]
+        secret: SecretArgs,
+        /// Print the EIP-712 typed data an Ethereum wallet signs instead
+        #[clap(long)]
+        typed_data: bool,
+        #[clap(flatten)]
+        params: ParamsArgs,
+    },
+    /// Check that every point of a batch contribution is in the right subgroup
+    CheckSubgroup {
+        #[clap(flatten)]
+        input: InputArgs,
+        #[clap(flatten)]
+        params: ParamsArgs,
+        /// Print the report as text or JSON
+        #[clap(long, value_enum, default_value = "text")]
+        format: ReportFormat,
+    },
+    /// Verify a batch transcript
+    Verify {
+        #[clap(flatten)]
+        input: InputArgs,
+        #[clap(flatten)]
+        params: ParamsArgs,
+        /// Also check that the ECDSA signature of each Ethereum participant recovers their address (recommended)
+        #[clap(long)]
+        ecdsa_signatures: bool,
+        /// Run the checks with both the blst and arkworks engines and report where they disagree
+        #[clap(long)]
+        cross_check: bool,
+        /// Check the witness chain with randomized multi-pairings, faster on large transcripts
+        #[clap(long)]
+        batched: bool,
+        /// Print the progress to stderr, ignored with --cross-check
+        #[clap(long)]
+        progress: bool,
+        /// Print the report as text or JSON
+        #[clap(long, value_enum, default_value = "text")]
+        format: ReportFormat,
+    },
+    /// Verify that an identity contributed to a batch transcript
+    Inclusion {
+        #[clap(flatten)]
+        input: InputArgs,
+        /// Participant identity, like "eth|0x..." or "git|<id>|<username>"
+        #[clap(long)]
+        identity: String,
+        /// Check the witness chain with randomized multi-pairings, faster on large transcripts
+        #[clap(long)]
+        batched: bool,
+    },
+    /// Verify the BLS signatures binding each contribution to its participant identity
+    BlsSignatures {
+        #[clap(flatten)]
+        input: InputArgs,
+    },
+    /// Verify that a sequencer receipt binds an identity to the potPubkeys of a secret
+    VerifyReceipt {
+        /// Path of the receipt saved by `sequencer contribute`, or "-" for stdin
+        #[clap(long, default_value = "-")]
+        receipt: PathBuf,
+        /// Address of the sequencer account, given by `sequencer status`
+        #[clap(long)]
+        sequencer_address: String,
+        #[clap(flatten)]
+        secret: SecretArgs,
+        /// Expected participant identity, like "eth|0x..." or "git|<id>|<username>"
+        #[clap(long)]
+        identity: String,
+        #[clap(flatten)]
+        params: ParamsArgs,
+    },
+    /// Verify a batch transcript and export a sub-ceremony as a c-kzg trusted_setup.txt
+    ExportTrustedSetup {
+        #[clap(flatten)]
+        input: InputArgs,
+        /// Path of the trusted setup, or "-" for stdout
+        #[clap(short, long, default_value = "-")]
+        output: PathBuf,
+        /// Index of the exported sub-ceremony
+        #[clap(long, default_value = "0")]
+        sub_ceremony: usize,
+        #[clap(flatten)]
+        params: ParamsArgs,
+    },
+    /// Verify a batch transcript and export a sub-ceremony as a snarkjs .ptau file, without the alpha and beta sections
+    ExportPtau {
+        #[clap(flatten)]
+        input: InputArgs,
+        /// Path of the ptau file, or "-" for stdout
+        #[clap(short, long, default_value = "-")]
+        output: PathBuf,
+        /// Index of the exported sub-ceremony
+        #[clap(long, default_value = "0")]
+        sub_ceremony: usize,
+        #[clap(flatten)]
+        params: ParamsArgs,
+    },
+    /// Talk to a ceremony sequencer
+    Sequencer(SequencerArgs),
+    /// Convert a batch contribution or transcript between JSON and the binary format
+    Convert {
+        /// Path of the JSON or binary input, or "-" for stdin
+        #[clap(short, long, default_value = "-")]
+        input: PathBuf,
+        /// Path of the converted file, or "-" for stdout
+        #[clap(short, long, default_value = "-")]
+        output: PathBuf,
+        /// Format of the converted file
+        #[clap(long, value_enum)]
+        to: Format,
+    },
+}
+
+#[derive(Args)]
+struct SequencerArgs {
+    /// Base URL of the sequencer
+    #[clap(long, default_value = "https://seq.ceremony.ethereum.org")]
+    url: String,
+    /// Timeout of the HTTP connections, in seconds
+    #[clap(long, default_value = "60")]
+    timeout: u64,
+    #[clap(subcommand)]
+    command: SequencerCommand,
+}
+
+// parsed once, the size of the variants doesn't matter
+#[allow(clippy::large_enum_variant)]
+#[derive(Subcommand)]
+enum SequencerCommand {
+    /// Print the lobby size and the number of contributions
+    Status,
+    /// Print the links to sign in and get a session id
+    Login {
+        /// Where the sequencer sends the session after the sign in
+        #[clap(long)]
+        redirect_to: Option<String>,
+    },
+    /// Download the current transcript
+    Transcript {
+        /// Path of the transcript, or "-" for stdout
+        #[clap(short, long, default_value = "-")]
+        output: PathBuf,
+    },
+    /// Wait for your turn, contribute and save the receipt
+    Contribute {
+        /// Session id given by the sequencer after the sign in
+        #[clap(long)]
+        session_id: String,
+        /// Participant identity of the session, like "eth|0x..." or "git|<id>|<username>"
+        #[clap(long)]
+        identity: String,
+        #[clap(flatten)]
+        secret: SecretArgs,
+        #[clap(flatten)]
+        params: ParamsArgs,
+        /// Seconds between two requests to the lobby
+        #[clap(long, default_value = "10")]
+        poll_interval: u64,
+        /// Path of the receipt, or "-" for stdout
+        #[clap(long, default_value = "-")]
+        receipt: PathBuf,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Json,
+    Binary,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ReportFormat {
+    Text,
+    Json,
+}
+
+#[derive(Args)]
+struct InputArgs {
+    /// Path of the JSON input, or "-" for stdin
+    #[clap(short, long, default_value = "-")]
+    input: PathBuf,
+}
+
+#[derive(Args)]
+struct ParamsArgs {
+    /// Ceremony shape: "ethereum" or a list of sub-ceremonies like "4096x65,8192x65"
+    #[clap(long = "params", default_value = "ethereum", value_parser)]
+    params: CeremonyParams,
+}
+
+#[derive(Args)]
+#[clap(group(ArgGroup::new("secret_source").required(true)))]
+struct SecretArgs {
+    /// Secret as a 32 bytes hex string (visible to other local users, prefer the alternatives)
+    #[clap(long, group = "secret_source")]
+    secret: Option<String>,
+    /// Read the hex secret from a file
+    #[clap(long, value_name = "PATH", group = "secret_source")]
+    secret_file: Option<PathBuf>,
+    /// Read the hex secret from an environment variable
+    #[clap(long, value_name = "VAR", group = "secret_source")]
+    secret_env: Option<String>,
+    /// Derive a fresh secret from OS randomness, mixed with the optional entropy below
+    #[clap(long, group = "secret_source")]
+    entropy: bool,
+    /// Text mixed into the derived secret
+    #[clap(long, value_name = "TEXT", requires = "entropy")]
+    entropy_text: Option<String>,
+    /// Harden --entropy-text with Argon2id, costs like "m=65536,t=3,p=1" or "default"
+    #[clap(long, value_name = "COSTS", requires = "entropy-text", value_parser)]
+    kdf: Option<KdfParams>,
+    /// File mixed into the derived secret, like a dump of a hardware RNG
+    #[clap(long, value_name = "PATH", requires = "entropy")]
+    entropy_file: Option<PathBuf>,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let start = Instant::now();
+    let result = run(cli.command, cli.engine);
+    if cli.verbose {
+        eprintln!("time: {:?}", start.elapsed());
+    }
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(EXIT_CHECK_FAILED),
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::from(exit_code(&error))
+        }
+    }
+}
+
+/// Runs a subcommand. Returns `false` when a check did not pass
+fn run(command: Command, engine: EngineKind) -> Result<bool> {
+    let is_valid = match command {
+        Command::Contribute { input, output, secret, identity, params, ecdsa_signature, progress } => {
+            let json = read_input(&input.input)?;
+            let string_secret = secret.read()?;
+            let mut contribution = contribute_with_progress(
+                json.clone(),
+                &string_secret,
+                &identity,
+                &params.params,
+                engine,
+                to_progress(progress),
+                &CancelToken::new(),
+            )?;
+            // catch a corrupted computation before it is shared
+            verify_update_with_string(json, contribution.clone(), engine)?;
+            if let Some(signature) = ecdsa_signature {
+                contribution = add_ecdsa_signature_with_string(contribution, &signature, &identity)?;
+            }
+            write_output(&output, &contribution)?;
+            true
+        },
+        Command::Pubkeys { secret, typed_data, params } => {
+            let string_secret = secret.read()?;
+            if typed_data {
+                let typed_data = pot_pubkeys_typed_data_with_string(&string_secret, &params.params, engine)?;
+                println!("{}", typed_data);
+            } else {
+                let mut pot_pubkeys = get_pot_pubkeys_with_string(&string_secret, engine)?;
+                // one per sub-ceremony, like in the typed data
+                pot_pubkeys.truncate(params.params.sizes().len());
+                println!("{}", serde_json::to_string_pretty(&pot_pubkeys)?);
+            }
+            true
+        },
+        Command::CheckSubgroup { input, params, format } => {
+            let json = read_input(&input.input)?;
+            let report = check_subgroup_report_with_string(json, &params.params, engine)?;
+            print_report(&report, format)?;
+            report.is_valid
+        },
+        Command::Verify { input, params, ecdsa_signatures, cross_check, batched, progress, format } => {
+            // read once, the audits below run on the same transcript
+            let batch_transcript = read_batch_transcript(open_input(&input.input)?)?;
+            let ecdsa_report = if ecdsa_signatures {
+                Some(verify_ecdsa_signatures(&batch_transcript)?)
+            } else {
+                None
+            };
+            if cross_check {
+                let mut report = cross_check_transcript(&batch_transcript, &params.params)?;
+                if let Some(ecdsa_report) = ecdsa_report {
+                    report = report.with_ecdsa_signatures(ecdsa_report);
+                }
+                print_report(&report, format)?;
+                report.is_valid()
+            } else {
+                let mut report = report_batch_transcript_with_progress(
+                    &batch_transcript,
+                    &params.params,
+                    engine,
+                    witness_check(batched),
+                    to_progress(progress),
+                    &CancelToken::new(),
+                )?;
+                if let Some(ecdsa_report) = ecdsa_report {
+                    report = report.with_ecdsa_signatures(ecdsa_report);
+                }
+                print_report(&report, format)?;
+                report.is_valid
+            }
+        },
+        Command::Inclusion { input, identity, batched } => {
+            let reader = open_input(&input.input)?;
+            let inclusion = verify_inclusion_with_reader(reader, &identity, engine, witness_check(batched))?;
+            println!("{}", serde_json::to_string_pretty(&inclusion)?);
+            inclusion.is_included()
+        },
+        Command::BlsSignatures { input } => {
+            let reader = open_input(&input.input)?;
+            let reports = verify_bls_signatures_with_reader(reader, engine)?;
+            println!("{}", serde_json::to_string_pretty(&reports)?);
+            let count = | predicate: fn(&BlsSignatureReport) -> bool | reports.iter().filter(| report | predicate(report)).count();
+            eprintln!(
+                "{} identity-bound, {} with invalid signatures, out of {} contributions",
+                count(BlsSignatureReport::is_identity_bound),
+                count(BlsSignatureReport::has_invalid),
+                reports.len(),
+            );
+            count(BlsSignatureReport::has_invalid) == 0
+        },
+        Command::VerifyReceipt { receipt, sequencer_address, secret, identity, params } => {
+            let contribution_receipt: ContributionReceipt = serde_json::from_str(&read_input(&receipt)?)?;
+            let string_secret = secret.read()?;
+            let result = verify_receipt_with_string(
+                &contribution_receipt.receipt,
+                &contribution_receipt.signature,
+                &sequencer_address,
+                &string_secret,
+                &identity,
+                &params.params,
+                engine,
+            );
+            let is_valid = match result {
+                Ok(_) => true,
+                Err(WrapperError::Receipt(message)) => {
+                    eprintln!("{}", message);
+                    false
+                },
+                Err(error) => return Err(error),
+            };
+            println!("Receipt is valid: {:?}", is_valid);
+            is_valid
+        },
+        Command::ExportTrustedSetup { input, output, sub_ceremony, params } => {
+            let reader = open_input(&input.input)?;
+            let trusted_setup = export_trusted_setup_with_reader(reader, sub_ceremony, &params.params, engine)?;
+            create_output(&output)?.write_all(trusted_setup.as_bytes())?;
+            true
+        },
+        Command::ExportPtau { input, output, sub_ceremony, params } => {
+            let reader = open_input(&input.input)?;
+            export_ptau_with_reader(reader, create_output(&output)?, sub_ceremony, &params.params, engine)?;
+            true
+        },
+        Command::Sequencer(args) => {
+            let mut config = SequencerConfig::new(&args.url);
+            config.request_timeout = Duration::from_secs(args.timeout);
+            config.engine = engine;
+            if let SequencerCommand::Contribute { poll_interval, .. } = &args.command {
+                config.poll_interval = Duration::from_secs(*poll_interval);
+            }
+            let client = SequencerClient::new(config);
+            match args.command {
+                SequencerCommand::Status => {
+                    println!("{}", serde_json::to_string_pretty(&client.status()?)?);
+                },
+                SequencerCommand::Login { redirect_to } => {
+                    let links = client.request_link(redirect_to.as_deref())?;
+                    println!("Sign in with Ethereum: {}", links.eth_auth_url);
+                    println!("Sign in with GitHub: {}", links.github_auth_url);
+                    println!("Then pass the session id you get to `sequencer contribute`");
+                },
+                SequencerCommand::Transcript { output } => {
+                    let transcript = client.current_state()?;
+                    serde_json::to_writer(create_output(&output)?, &transcript)?;
+                },
+                SequencerCommand::Contribute { session_id, identity, secret, params, receipt, .. } => {
+                    let string_secret = secret.read()?;
+                    eprintln!("waiting for our turn in the lobby");
+                    let contribution_receipt = client.participate(&session_id, &string_secret, &identity, &params.params)?;
+                    write_output(&receipt, &serde_json::to_string_pretty(&contribution_receipt)?)?;
+                },
+            }
+            true
+        },
+        Command::Convert { input, output, to } => {
+            let mut bytes = Vec::new();
+            open_input(&input)?.read_to_end(&mut bytes)?;
+            // the input format is detected from its first bytes
+            let batch = Batch::from_bytes(&bytes)?;
+            drop(bytes);
+            match to {
+                Format::Json => write_output(&output, &batch.to_json()?)?,
+                Format::Binary => batch.write_binary(create_output(&output)?)?,
+            }
+            true
+        },
+    };
+    Ok(is_valid)
+}
+
+impl SecretArgs {
+    fn read(&self) -> Result<String> {
+        if self.entropy {
+            let mut mixer = EntropyMixer::new();
+            if let Some(path) = &self.entropy_file {
+                mixer.add_file(path)?;
+            }
+            mixer.add_os_randomness()?;
+            // added last, so the passphrase is salted with the other sources
+            match (&self.entropy_text, &self.kdf) {
+                (Some(text), Some(params)) => { mixer.add_passphrase(text, params)?; },
+                (Some(text), None) => { mixer.add_text(text); },
+                (None, _) => {},
+            }
+            return mixer.finalize_to_string();
+        }
+        let secret = match (&self.secret, &self.secret_file, &self.secret_env) {
+            (Some(secret), _, _) => secret.clone(),
+            (_, Some(path), _) => fs::read_to_string(path)?,
+            (_, _, Some(var)) => std::env::var(var)
+                .map_err(|error| io::Error::new(io::ErrorKind::NotFound, format!("{}: {}", var, error)))?,
+            // clap makes one of the sources required
+            (None, None, None) => unreachable!(),
+        };
+        Ok(secret.trim().trim_start_matches("0x").to_string())
+    }
+}
+
+fn read_input(path: &Path) -> Result<String> {
+    let mut content = String::new();
+    if path.as_os_str() == "-" {
+        io::stdin().read_to_string(&mut content)?;
+    } else {
+        content = fs::read_to_string(path)?;
+    }
+    Ok(content)
+}
+
+/// Transcripts are streamed instead of read into memory, they can be huge
+fn open_input(path: &Path) -> Result<Box<dyn Read>> {
+    if path.as_os_str() == "-" {
+        Ok(Box::new(io::stdin().lock()))
+    } else {
+        Ok(Box::new(fs::File::open(path)?))
+    }
+}
+
+fn write_output(path: &Path, content: &str) -> Result<()> {
+    if path.as_os_str() == "-" {
+        let mut stdout = io::stdout();
+        stdout.write_all(content.as_bytes())?;
+        stdout.write_all(b"\n")?;
+    } else {
+        fs::write(path, content)?;
+    }
+    Ok(())
+}
+
+fn create_output(path: &Path) -> Result<Box<dyn Write>> {
+    if path.as_os_str() == "-" {
+        Ok(Box::new(io::BufWriter::new(io::stdout().lock())))
+    } else {
+        Ok(Box::new(io::BufWriter::new(fs::File::create(path)?)))
+    }
+}
+
+fn witness_check(batched: bool) -> WitnessCheck {
+    if batched {
+        WitnessCheck::Batched
+    } else {
+        WitnessCheck::PerEntry
+    }
+}
+
+fn to_progress(progress: bool) -> &'static dyn Progress {
+    if progress {
+        &print_progress
+    } else {
+        &NoProgress
+    }
+}
+
+/// On stderr, stdout only carries the output
+fn print_progress(update: &ProgressUpdate) {
+    eprintln!(
+        "{:5.1}% {} of sub-ceremony {}/{}",
+        update.percentage,
+        update.stage,
+        update.sub_ceremony + 1,
+        update.num_sub_ceremonies,
+    );
+}
+
+/// In JSON, stdout only carries the report
+fn print_report<T: Serialize + Display>(report: &T, format: ReportFormat) -> Result<()> {
+    match format {
+        ReportFormat::Text => println!("{}", report),
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(report)?),
+    }
+    Ok(())
+}
+
+fn exit_code(error: &WrapperError) -> u8 {
+    match error {
+        WrapperError::Ceremony(_) | WrapperError::SubCeremony { .. } => EXIT_CEREMONY_ERROR,
+        WrapperError::Json(_)
+        | WrapperError::Hex(_)
+        | WrapperError::Binary(_)
+        | WrapperError::Point(_)
+        | WrapperError::Identity(_)
+        | WrapperError::Params(_)
+        | WrapperError::Engine(_)
+        | WrapperError::Signature(_)
+        | WrapperError::Witness(_) => EXIT_INVALID_INPUT,
+        WrapperError::Receipt(_) => EXIT_CHECK_FAILED,
+        WrapperError::Io(_) | WrapperError::Entropy(_) => EXIT_IO_ERROR,
+        WrapperError::Sequencer(_) => EXIT_SEQUENCER_ERROR,
+        WrapperError::Cancelled => EXIT_CANCELLED,
+    }
+}
@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use k256::ecdsa::SigningKey;
+use serde::Serialize;
+use tiny_http::{Header, Method, Request, Response, Server};
+use kzg_ceremony_crypto::{
+    BatchContribution,
+    BatchTranscript,
+    Identity,
+};
+use crate::engine::with_engine;
+use crate::eth::{address_of, address_to_string, eip191_digest, sign_digest};
+use crate::{
+    AuthLinks,
+    AuthSession,
+    CeremonyParams,
+    ContributionReceipt,
+    EngineKind,
+    IdToken,
+    Receipt,
+    Result,
+    SequencerStatus,
+    WrapperError,
+    verify_update,
+};
+
+/// Expiry of the ID tokens, the mock never checks it
+const TOKEN_EXPIRY: u64 = u64::MAX;
+
+struct State {
+    transcript: BatchTranscript,
+    params: CeremonyParams,
+    /// Session id to participant identity
+    sessions: HashMap<String, Identity>,
+    /// Session whose turn it is
+    current: Option<String>,
+    num_sessions: usize,
+    /// Signs the receipts
+    key: SigningKey,
+}
+
+/**
+ * In-process stand-in for the ceremony sequencer, to test contribution flows offline.
+ *
+ * It speaks the same HTTP API as the real one. Signing in is faked: the `code` of
+ * `auth/callback/eth` is taken as the address and the one of `auth/callback/github`
+ * as the username. Uploaded contributions are validated and checked against the
+ * current one before being added to the transcript. Receipts are signed with a
+ * fresh key, whose address is given by `info/status`
+ */
+pub struct MockSequencer {
+    url: String,
+    state: Arc<Mutex<State>>,
+    server: Arc<Server>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockSequencer {
+    /// Listens on a free local port, with a new transcript of the ceremony shape
+    pub fn start(params: &CeremonyParams) -> Result<Self> {
+        Self::bind("127.0.0.1:0", params)
+    }
+
+    pub fn bind(address: &str, params: &CeremonyParams) -> Result<Self> {
+        let server = Server::http(address).map_err(| error | WrapperError::Sequencer(error.to_string()))?;
+        let url = match server.server_addr().to_ip() {
+            Some(address) => format!("http://{}", address),
+            None => return Err(WrapperError::Sequencer(String::from("the mock sequencer needs an IP address"))),
+        };
+        let key = loop {
+            let mut bytes = [0u8; 32];
+            getrandom::getrandom(&mut bytes).map_err(| error | WrapperError::Entropy(error.to_string()))?;
+            // fails for the zero scalar and values above the group order
+            if let Ok(key) = SigningKey::from_bytes(&bytes) {
+                break key;
+            }
+        };
+        let state = Arc::new(Mutex::new(State {
+            transcript: BatchTranscript::new(params.sizes()),
+            params: params.clone(),
+            sessions: HashMap::new(),
+            current: None,
+            num_sessions: 0,
+            key,
+        }));
+        let server = Arc::new(server);
+
+        let handle = {
+            let (server, state, url) = (server.clone(), state.clone(), url.clone());
+            thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    handle_request(request, &state, &url);
+                }
+            })
+        };
+        Ok(Self { url, state, server, handle: Some(handle) })
+    }
+
+    /// Base URL to give to `SequencerConfig::new`
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Address of the key signing the receipts
+    pub fn sequencer_address(&self) -> String {
+        address_to_string(&address_of(&lock(&self.state).key.verifying_key()))
+    }
+
+    /// Copy of the transcript with all the accepted contributions
+    pub fn transcript(&self) -> BatchTranscript {
+        lock(&self.state).transcript.clone()
+    }
+}
+
+impl Drop for MockSequencer {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/**
+ * Util functions
+ */
+fn lock(state: &Mutex<State>) -> MutexGuard<'_, State> {
+    // a panic while handling a request leaves the state usable
+    state.lock().unwrap_or_else(| poisoned | poisoned.into_inner())
+}
+
+fn handle_request(mut request: Request, state: &Mutex<State>, url: &str) {
+    let mut body = String::new();
+    let (status, response) = match request.as_reader().read_to_string(&mut body) {
+        Ok(_) => route(&request, &body, &mut lock(state), url),
+        Err(error) => error_response(400, "RequestError::InvalidBody", &error.to_string()),
+    };
+    let header = Header::from_bytes("Content-Type", "application/json").unwrap();
+    let response = Response::from_string(response).with_status_code(status).with_header(header);
+    let _ = request.respond(response);
+}
+
+fn route(request: &Request, body: &str, state: &mut State, url: &str) -> (u16, String) {
+    let (path, query) = request.url().split_once('?').unwrap_or((request.url(), ""));
+    let query: HashMap<&str, &str> = query.split('&').filter_map(| pair | pair.split_once('=')).collect();
+    let session_id = request
+        .headers()
+        .iter()
+        .find(| header | header.field.equiv("Authorization"))
+        .and_then(| header | header.value.as_str().strip_prefix("Bearer "))
+        .map(String::from);
+
+    match (request.method(), path) {
+        (Method::Get, "/info/status") => json_response(&SequencerStatus {
+            lobby_size: state.sessions.len(),
+            num_contributions: state.transcript.participant_ids.len() - 1,
+            sequencer_address: address_to_string(&address_of(&state.key.verifying_key())),
+        }),
+        (Method::Get, "/info/current_state") => json_response(&state.transcript),
+        (Method::Get, "/auth/request_link") => json_response(&AuthLinks {
+            eth_auth_url: format!("{}/auth/callback/eth?code=<address>&state=mock", url),
+            github_auth_url: format!("{}/auth/callback/github?code=<username>&state=mock", url),
+        }),
+        (Method::Get, "/auth/callback/eth") => match query.get("code").map(| code | Identity::eth_from_str(code)) {
+            Some(Ok(identity)) => sign_in(state, identity, "ethereum"),
+            _ => error_response(400, "AuthError::InvalidAuthCode", "the code should be an Ethereum address"),
+        },
+        (Method::Get, "/auth/callback/github") => match query.get("code") {
+            Some(username) => {
+                let identity = format!("git|{}|{}", state.num_sessions, username).parse::<Identity>();
+                match identity {
+                    Ok(identity) => sign_in(state, identity, "github"),
+                    Err(_) => error_response(400, "AuthError::InvalidAuthCode", "invalid username"),
+                }
+            },
+            None => error_response(400, "AuthError::InvalidAuthCode", "missing code"),
+        },
+        (Method::Post, "/lobby/try_contribute") => try_contribute(state, session_id),
+        (Method::Post, "/contribute") => contribute(state, session_id, body),
+        (Method::Post, "/contribute/abort") => abort_contribution(state, session_id),
+        _ => error_response(404, "NotFound", path),
+    }
+}
+
+fn sign_in(state: &mut State, identity: Identity, provider: &str) -> (u16, String) {
+    state.num_sessions += 1;
+    let session_id = format!("mock-session-{}", state.num_sessions);
+    let id_token = IdToken {
+        sub: identity.to_string(),
+        nickname: identity.to_string(),
+        provider: provider.to_string(),
+        exp: TOKEN_EXPIRY,
+    };
+    state.sessions.insert(session_id.clone(), identity);
+    json_response(&AuthSession { id_token, session_id })
+}
+
+fn try_contribute(state: &mut State, session_id: Option<String>) -> (u16, String) {
+    let session_id = match session_id {
+        Some(session_id) if state.sessions.contains_key(&session_id) => session_id,
+        _ => return error_response(400, "TryContributeError::UnknownSessionId", "unknown session id"),
+    };
+    match &state.current {
+        Some(current) if *current != session_id => error_response(
+            200,
+            "TryContributeError::AnotherContributionInProgress",
+            "another contribution in progress",
+        ),
+        _ => {
+            state.current = Some(session_id);
+            json_response(&state.transcript.contribution())
+        },
+    }
+}
+
+fn contribute(state: &mut State, session_id: Option<String>, body: &str) -> (u16, String) {
+    let session_id = match session_id {
+        Some(session_id) if state.current.as_ref() == Some(&session_id) => session_id,
+        _ => return error_response(400, "ContributeError::NotUsersTurn", "not your turn"),
+    };
+    // the turn ends with the upload, valid or not
+    state.current = None;
+    let identity = state.sessions.remove(&session_id).unwrap();
+
+    let previous = state.transcript.contribution();
+    let result = serde_json::from_str::<BatchContribution>(body)
+        .map_err(WrapperError::from)
+        .and_then(| mut contribution | {
+            state.params.check_contribution(&contribution)?;
+            with_engine!(EngineKind::default(), E => {
+                contribution.validate::<E>()?;
+                verify_update::<E>(&previous, &contribution)
+            })?;
+            Ok(contribution)
+        });
+    let contribution = match result {
+        Ok(contribution) => contribution,
+        Err(error) => return error_response(400, "ContributeError::InvalidContribution", &error.to_string()),
+    };
+
+    let receipt = Receipt { identity: identity.clone(), witness: contribution.receipt() };
+    for (transcript, sub_contribution) in state.transcript.transcripts.iter_mut().zip(contribution.contributions) {
+        transcript.witness.products.push(sub_contribution.powers.g1[1]);
+        transcript.witness.pubkeys.push(sub_contribution.pot_pubkey);
+        transcript.witness.signatures.push(sub_contribution.bls_signature);
+        transcript.powers = sub_contribution.powers;
+    }
+    state.transcript.participant_ids.push(identity);
+    state.transcript.participant_ecdsa_signatures.push(contribution.ecdsa_signature);
+
+    let receipt = serde_json::to_string(&receipt).unwrap();
+    let signature = sign_digest(&state.key, &eip191_digest(receipt.as_bytes()));
+    json_response(&ContributionReceipt { receipt, signature })
+}
+
+fn abort_contribution(state: &mut State, session_id: Option<String>) -> (u16, String) {
+    match session_id {
+        Some(session_id) if state.current.as_ref() == Some(&session_id) => {
+            state.current = None;
+            state.sessions.remove(&session_id);
+            (200, String::from("{}"))
+        },
+        _ => error_response(400, "ContributeError::NotUsersTurn", "not your turn"),
+    }
+}
+
+fn json_response<T: Serialize>(value: &T) -> (u16, String) {
+    (200, serde_json::to_string(value).unwrap())
+}
+
+fn error_response(status: u16, code: &str, error: &str) -> (u16, String) {
+    (status, serde_json::json!({ "code": code, "error": error }).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kzg_ceremony_crypto::DefaultEngine;
+    use crate::{AuthProvider, SequencerClient, SequencerConfig, contribute_with_string, verify_receipt_with_string};
+
+    const SECRET: &str = "6b86b273ff34fce19d6b804eff5a3f5747ada4eaa22f1d49c01e52ddb7875b4b";
+
+    #[test]
+    fn mock_sequencer_test() {
+        let params = CeremonyParams::new(vec![(4, 2), (8, 3)]).unwrap();
+        let sequencer = MockSequencer::start(&params).unwrap();
+        let client = SequencerClient::new(SequencerConfig::new(sequencer.url()));
+
+        let alice = client.auth_callback(AuthProvider::Ethereum, "0x000000000000000000000000000000000000dead", "mock").unwrap();
+        let bob = client.auth_callback(AuthProvider::Github, "bob", "mock").unwrap();
+        assert_eq!(client.status().unwrap().lobby_size, 2);
+
+        // one participant at a time
+        let contribution = client.try_contribute(&alice.session_id).unwrap().unwrap();
+        assert_eq!(client.try_contribute(&bob.session_id).unwrap(), None);
+
+        // an update that adds two secrets under one pubkey is rejected and ends the turn
+        let once = contribute_with_string(contribution, SECRET, &alice.id_token.sub, &params, EngineKind::default()).unwrap();
+        let twice = contribute_with_string(once, SECRET, &alice.id_token.sub, &params, EngineKind::default()).unwrap();
+        let result = client.contribute(&alice.session_id, &twice);
+        assert!(matches!(result, Err(WrapperError::Sequencer(message)) if message.contains("InvalidContribution")));
+        assert_eq!(client.status().unwrap().num_contributions, 0);
+
+        let receipt = client.participate(&bob.session_id, SECRET, &bob.id_token.sub, &params).unwrap();
+        let address = client.status().unwrap().sequencer_address;
+        assert_eq!(address, sequencer.sequencer_address());
+        let verified = verify_receipt_with_string(&receipt.receipt, &receipt.signature, &address, SECRET, &bob.id_token.sub, &params, EngineKind::default()).unwrap();
+        assert_eq!(verified.identity.to_string(), bob.id_token.sub);
+
+        let transcript = client.current_state().unwrap();
+        assert_eq!(transcript, sequencer.transcript());
+        assert_eq!(transcript.participant_ids.len(), 2);
+        assert_eq!(transcript.transcripts[1].witness.pubkeys.len(), 2);
+        assert!(transcript.verify_self::<DefaultEngine>(params.sizes().to_vec()).is_ok());
+    }
+}
@@ -0,0 +1,67 @@
+// rayon with the `parallel` feature, otherwise sequential stand-ins of the rayon
+// methods we use, for the single-threaded wasm build. The modules import
+// `crate::parallel::prelude::*` and keep the same code in both builds
+
+#[cfg(feature = "parallel")]
+pub(crate) use rayon::{current_num_threads, prelude};
+
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn current_num_threads() -> usize {
+    1
+}
+
+#[cfg(not(feature = "parallel"))]
+pub(crate) mod prelude {
+    use std::slice::{Chunks, ChunksMut, Iter, IterMut};
+
+    pub(crate) trait IntoParallelIterator: IntoIterator + Sized {
+        fn into_par_iter(self) -> Self::IntoIter {
+            self.into_iter()
+        }
+    }
+
+    impl<I: IntoIterator> IntoParallelIterator for I {}
+
+    pub(crate) trait ParallelSlice<T> {
+        fn par_iter(&self) -> Iter<'_, T>;
+        fn par_chunks(&self, chunk_size: usize) -> Chunks<'_, T>;
+    }
+
+    impl<T> ParallelSlice<T> for [T] {
+        fn par_iter(&self) -> Iter<'_, T> {
+            self.iter()
+        }
+
+        fn par_chunks(&self, chunk_size: usize) -> Chunks<'_, T> {
+            self.chunks(chunk_size)
+        }
+    }
+
+    pub(crate) trait ParallelSliceMut<T> {
+        fn par_iter_mut(&mut self) -> IterMut<'_, T>;
+        fn par_chunks_mut(&mut self, chunk_size: usize) -> ChunksMut<'_, T>;
+    }
+
+    impl<T> ParallelSliceMut<T> for [T] {
+        fn par_iter_mut(&mut self) -> IterMut<'_, T> {
+            self.iter_mut()
+        }
+
+        fn par_chunks_mut(&mut self, chunk_size: usize) -> ChunksMut<'_, T> {
+            self.chunks_mut(chunk_size)
+        }
+    }
+
+    /// The rayon searches that have no `Iterator` counterpart with the same name
+    pub(crate) trait ParallelIterator: Iterator + Sized {
+        fn find_first<P: FnMut(&Self::Item) -> bool>(mut self, predicate: P) -> Option<Self::Item> {
+            self.find(predicate)
+        }
+
+        fn find_map_first<B, F: FnMut(Self::Item) -> Option<B>>(mut self, f: F) -> Option<B> {
+            self.find_map(f)
+        }
+    }
+
+    impl<I: Iterator> ParallelIterator for I {}
+}
@@ -0,0 +1,124 @@
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use kzg_ceremony_crypto::{BatchContribution, CeremonyError};
+use crate::{Result, WrapperError};
+
+/// Number of (G1, G2) powers of each sub-ceremony of the Ethereum KZG ceremony
+const ETHEREUM_SIZES: [(usize, usize); 4] = [(4096, 65), (8192, 65), (16384, 65), (32768, 65)];
+
+/**
+ * Shape of a ceremony: the number of G1 and G2 powers of each sub-ceremony
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "Vec<(usize, usize)>", into = "Vec<(usize, usize)>")]
+pub struct CeremonyParams {
+    sizes: Vec<(usize, usize)>,
+}
+
+impl CeremonyParams {
+    /// Custom ceremony with one `(num_g1_powers, num_g2_powers)` entry per sub-ceremony
+    pub fn new(sizes: Vec<(usize, usize)>) -> Result<Self> {
+        if sizes.is_empty() {
+            return Err(WrapperError::Params(String::from("at least one sub-ceremony is needed")));
+        }
+        if let Some(index) = sizes.iter().position(|(g1, g2)| *g1 == 0 || *g2 == 0) {
+            return Err(WrapperError::Params(format!("sub-ceremony {} has no powers", index)));
+        }
+        Ok(Self { sizes })
+    }
+
+    /// The Ethereum KZG ceremony: 4096, 8192, 16384 and 32768 G1 powers, 65 G2 powers each
+    pub fn ethereum() -> Self {
+        Self { sizes: ETHEREUM_SIZES.to_vec() }
+    }
+
+    pub fn sizes(&self) -> &[(usize, usize)] {
+        &self.sizes
+    }
+
+    /// Checks that a contribution has the shape of the ceremony
+    pub fn check_contribution(&self, contribution: &BatchContribution) -> Result<()> {
+        if contribution.contributions.len() != self.sizes.len() {
+            return Err(CeremonyError::UnexpectedNumContributions(
+                self.sizes.len(),
+                contribution.contributions.len(),
+            ).into());
+        }
+        for (index, (sub_contribution, (num_g1, num_g2))) in contribution
+            .contributions
+            .iter()
+            .zip(self.sizes.iter())
+            .enumerate()
+        {
+            let source = if sub_contribution.powers.g1.len() != *num_g1 {
+                CeremonyError::UnexpectedNumG1Powers(*num_g1, sub_contribution.powers.g1.len())
+            } else if sub_contribution.powers.g2.len() != *num_g2 {
+                CeremonyError::UnexpectedNumG2Powers(*num_g2, sub_contribution.powers.g2.len())
+            } else {
+                continue;
+            };
+            return Err(WrapperError::SubCeremony { index, source });
+        }
+        Ok(())
+    }
+}
+
+impl Default for CeremonyParams {
+    fn default() -> Self {
+        Self::ethereum()
+    }
+}
+
+impl TryFrom<Vec<(usize, usize)>> for CeremonyParams {
+    type Error = WrapperError;
+
+    fn try_from(sizes: Vec<(usize, usize)>) -> Result<Self> {
+        Self::new(sizes)
+    }
+}
+
+impl From<CeremonyParams> for Vec<(usize, usize)> {
+    fn from(params: CeremonyParams) -> Self {
+        params.sizes
+    }
+}
+
+/// Parses `ethereum` or a list of shapes like `4096x65,8192x65`
+impl FromStr for CeremonyParams {
+    type Err = WrapperError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.eq_ignore_ascii_case("ethereum") {
+            return Ok(Self::ethereum());
+        }
+        let sizes = s
+            .split(',')
+            .map(|shape| {
+                let (g1, g2) = shape.trim().split_once('x')
+                .ok_or_else(|| WrapperError::Params(format!("expected <G1>x<G2>, found {:?}", shape)))?;
+                let g1 = g1.parse().map_err(|_| WrapperError::Params(format!("invalid G1 powers {:?}", g1)))?;
+                let g2 = g2.parse().map_err(|_| WrapperError::Params(format!("invalid G2 powers {:?}", g2)))?;
+                Ok((g1, g2))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Self::new(sizes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_params_test() {
+        assert_eq!(CeremonyParams::from_str("ethereum").unwrap(), CeremonyParams::ethereum());
+        let params = CeremonyParams::from_str("4096x65, 8x2").unwrap();
+        assert_eq!(params.sizes(), &[(4096, 65), (8, 2)]);
+
+        assert!(CeremonyParams::from_str("4096").is_err());
+        assert!(CeremonyParams::from_str("0x65").is_err());
+        assert!(serde_json::from_str::<CeremonyParams>("[]").is_err());
+        let json = serde_json::to_string(&params).unwrap();
+        assert_eq!(json, "[[4096,65],[8,2]]");
+    }
+}
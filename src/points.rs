@@ -0,0 +1,119 @@
+use ark_bls12_381::{Fq, Fq2, G1Affine, G2Affine};
+use ark_ff::{BigInteger, BigInteger384, PrimeField, Zero};
+use kzg_ceremony_crypto::{G1, G2};
+use crate::{Result, WrapperError};
+
+// Conversions between the compressed points of the ceremony and the arkworks
+// points, for the exports that need the group arithmetic
+
+/// Flags of the first byte of a compressed point, in the ZCash format
+const COMPRESSED_FLAG: u8 = 0x80;
+const INFINITY_FLAG: u8 = 0x40;
+const SIGN_FLAG: u8 = 0x20;
+
+pub(crate) fn point_error(message: &str) -> WrapperError {
+    WrapperError::Point(message.to_string())
+}
+
+/// Big-endian field element, without flags
+fn fq_from_bytes(bytes: &[u8]) -> Result<Fq> {
+    let mut limbs = [0u64; 6];
+    for (limb, chunk) in limbs.iter_mut().zip(bytes.rchunks(8)) {
+        *limb = u64::from_be_bytes(chunk.try_into().unwrap());
+    }
+    Fq::from_repr(BigInteger384(limbs)).ok_or_else(|| point_error("coordinate is not in the field"))
+}
+
+/// Strips the flags of a compressed point. Returns `None` for the point at infinity
+fn strip_flags(bytes: &mut [u8]) -> Result<Option<bool>> {
+    let flags = bytes[0];
+    if flags & COMPRESSED_FLAG == 0 {
+        return Err(point_error("point is not compressed"));
+    }
+    bytes[0] &= !(COMPRESSED_FLAG | INFINITY_FLAG | SIGN_FLAG);
+    if flags & INFINITY_FLAG != 0 {
+        if flags & SIGN_FLAG != 0 || bytes.iter().any(| byte | *byte != 0) {
+            return Err(point_error("malformed point at infinity"));
+        }
+        return Ok(None);
+    }
+    Ok(Some(flags & SIGN_FLAG != 0))
+}
+
+pub(crate) fn g1_from_bytes(point: &G1) -> Result<G1Affine> {
+    let mut bytes = point.0;
+    let greatest = match strip_flags(&mut bytes)? {
+        Some(greatest) => greatest,
+        None => return Ok(G1Affine::zero()),
+    };
+    let x = fq_from_bytes(&bytes)?;
+    G1Affine::get_point_from_x(x, greatest)
+        .filter(| point | point.is_in_correct_subgroup_assuming_on_curve())
+        .ok_or_else(|| point_error("G1 point is not in the subgroup"))
+}
+
+pub(crate) fn g2_from_bytes(point: &G2) -> Result<G2Affine> {
+    let mut bytes = point.0;
+    let greatest = match strip_flags(&mut bytes)? {
+        Some(greatest) => greatest,
+        None => return Ok(G2Affine::zero()),
+    };
+    // the imaginary part comes first
+    let x = Fq2::new(fq_from_bytes(&bytes[48..])?, fq_from_bytes(&bytes[..48])?);
+    G2Affine::get_point_from_x(x, greatest)
+        .filter(| point | point.is_in_correct_subgroup_assuming_on_curve())
+        .ok_or_else(|| point_error("G2 point is not in the subgroup"))
+}
+
+pub(crate) fn g1_to_bytes(point: &G1Affine) -> [u8; 48] {
+    let mut bytes = [0u8; 48];
+    if point.is_zero() {
+        bytes[0] = COMPRESSED_FLAG | INFINITY_FLAG;
+        return bytes;
+    }
+    bytes.copy_from_slice(&point.x.into_repr().to_bytes_be());
+    bytes[0] |= COMPRESSED_FLAG;
+    if point.y > -point.y {
+        bytes[0] |= SIGN_FLAG;
+    }
+    bytes
+}
+
+pub(crate) fn g2_to_bytes(point: &G2Affine) -> [u8; 96] {
+    let mut bytes = [0u8; 96];
+    if point.is_zero() {
+        bytes[0] = COMPRESSED_FLAG | INFINITY_FLAG;
+        return bytes;
+    }
+    // the imaginary part comes first
+    bytes[..48].copy_from_slice(&point.x.c1.into_repr().to_bytes_be());
+    bytes[48..].copy_from_slice(&point.x.c0.into_repr().to_bytes_be());
+    bytes[0] |= COMPRESSED_FLAG;
+    if point.y > -point.y {
+        bytes[0] |= SIGN_FLAG;
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ec::AffineCurve;
+
+    #[test]
+    fn point_encoding_test() {
+        // same encoding as the ceremony points
+        let g1 = G1Affine::prime_subgroup_generator();
+        let g2 = G2Affine::prime_subgroup_generator();
+        assert_eq!(g1_to_bytes(&g1), G1::one().0);
+        assert_eq!(g2_to_bytes(&g2), G2::one().0);
+        assert_eq!(g1_from_bytes(&G1::one()).unwrap(), g1);
+        assert_eq!(g2_from_bytes(&G2::one()).unwrap(), g2);
+        assert!(g1_from_bytes(&G1::zero()).unwrap().is_zero());
+        assert!(g2_from_bytes(&G2::zero()).unwrap().is_zero());
+
+        let mut uncompressed = G1::one();
+        uncompressed.0[0] &= !COMPRESSED_FLAG;
+        assert!(matches!(g1_from_bytes(&uncompressed), Err(WrapperError::Point(_))));
+    }
+}
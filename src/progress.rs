@@ -0,0 +1,192 @@
+use std::cell::Cell;
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use serde::Serialize;
+use crate::parallel::{current_num_threads, prelude::*};
+use crate::{Result, WrapperError};
+
+/// Step of a long operation
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Stage {
+    /// Adding the secret to the powers
+    Contribute,
+    Subgroup,
+    Pairing,
+    Witness,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Stage::Contribute => "contribution",
+            Stage::Subgroup => "subgroup check",
+            Stage::Pairing => "pairing check",
+            Stage::Witness => "witness check",
+        })
+    }
+}
+
+/**
+ * Where a long operation is, reported between chunks of work
+ */
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgressUpdate {
+    pub stage: Stage,
+    pub sub_ceremony: usize,
+    pub num_sub_ceremonies: usize,
+    /// Points, or witness entries, processed so far over every stage
+    pub points_processed: usize,
+    pub total_points: usize,
+    pub percentage: f64,
+}
+
+/**
+ * Receives the progress of a long operation. It is always called from the
+ * thread that started the operation, so it can hold a JS callback
+ */
+pub trait Progress {
+    fn update(&self, update: &ProgressUpdate);
+}
+
+impl<F: Fn(&ProgressUpdate)> Progress for F {
+    fn update(&self, update: &ProgressUpdate) {
+        self(update)
+    }
+}
+
+/// Reports nothing
+pub struct NoProgress;
+
+impl Progress for NoProgress {
+    fn update(&self, _update: &ProgressUpdate) {}
+}
+
+/**
+ * Stops a long operation at the next chunk of work, which then fails with
+ * `WrapperError::Cancelled`. Clones share the same state, so one can be
+ * cancelled from another thread
+ */
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Counts the work done and forwards it to the `Progress`
+pub(crate) struct Tracker<'a> {
+    progress: &'a dyn Progress,
+    cancel: &'a CancelToken,
+    num_sub_ceremonies: usize,
+    total_points: usize,
+    points_processed: Cell<usize>,
+}
+
+impl<'a> Tracker<'a> {
+    pub(crate) fn new(progress: &'a dyn Progress, cancel: &'a CancelToken, num_sub_ceremonies: usize, total_points: usize) -> Self {
+        Self { progress, cancel, num_sub_ceremonies, total_points, points_processed: Cell::new(0) }
+    }
+
+    pub(crate) fn check_cancelled(&self) -> Result<()> {
+        if self.cancel.is_cancelled() {
+            return Err(WrapperError::Cancelled);
+        }
+        Ok(())
+    }
+
+    /// Reports `points` more processed, then fails if the operation was cancelled
+    pub(crate) fn advance(&self, stage: Stage, sub_ceremony: usize, points: usize) -> Result<()> {
+        let points_processed = self.points_processed.get() + points;
+        self.points_processed.set(points_processed);
+        let percentage = if self.total_points == 0 {
+            100.0
+        } else {
+            100.0 * points_processed as f64 / self.total_points as f64
+        };
+        self.progress.update(&ProgressUpdate {
+            stage,
+            sub_ceremony,
+            num_sub_ceremonies: self.num_sub_ceremonies,
+            points_processed,
+            total_points: self.total_points,
+            percentage,
+        });
+        self.check_cancelled()
+    }
+
+    /**
+     * Runs `check` over the chunks of `range`, a wave of chunks in parallel at a time,
+     * and reports between the waves. Returns the first failure, in the order of the range
+     */
+    pub(crate) fn run_chunked<T, F>(
+        &self,
+        stage: Stage,
+        sub_ceremony: usize,
+        range: Range<usize>,
+        chunk_size: usize,
+        check: F,
+    ) -> Result<Option<T>>
+    where
+        T: Send,
+        F: Fn(Range<usize>) -> Option<T> + Sync,
+    {
+        let chunks: Vec<Range<usize>> = range
+            .clone()
+            .step_by(chunk_size)
+            .map(| start | start..(start + chunk_size).min(range.end))
+            .collect();
+        for wave in chunks.chunks(current_num_threads().max(1)) {
+            self.check_cancelled()?;
+            let failure = wave.par_iter().find_map_first(| chunk | check(chunk.clone()));
+            if failure.is_some() {
+                return Ok(failure);
+            }
+            let points = wave.iter().map(| chunk | chunk.len()).sum();
+            self.advance(stage, sub_ceremony, points)?;
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    #[test]
+    fn tracker_test() {
+        let updates = RefCell::new(Vec::new());
+        let progress = | update: &ProgressUpdate | updates.borrow_mut().push(update.clone());
+        let cancel = CancelToken::new();
+        let tracker = Tracker::new(&progress, &cancel, 2, 40);
+
+        let failure = tracker.run_chunked(Stage::Subgroup, 0, 0..30, 4, | chunk | chunk.clone().find(| i | *i == 13 || *i == 27)).unwrap();
+        assert_eq!(failure, Some(13));
+        assert!(updates.borrow().iter().all(| update | update.points_processed <= 12));
+
+        let failure = tracker.run_chunked(Stage::Witness, 1, 1..11, 3, | _ | None::<usize>).unwrap();
+        assert_eq!(failure, None);
+        let last = updates.borrow().last().cloned().unwrap();
+        assert_eq!((last.stage, last.sub_ceremony, last.num_sub_ceremonies), (Stage::Witness, 1, 2));
+        assert!(last.points_processed >= 10 && last.percentage <= 100.0);
+
+        // a clone cancels the operation at the next chunk
+        cancel.clone().cancel();
+        let result = tracker.run_chunked(Stage::Pairing, 0, 0..10, 2, | _ | None::<usize>);
+        assert!(matches!(result, Err(WrapperError::Cancelled)));
+    }
+}
@@ -0,0 +1,296 @@
+use std::io::{self, Read, Write};
+use ark_bls12_381::{Fq, Fq2, G1Affine, G2Affine};
+use ark_ff::{BigInteger, BigInteger384, FpParameters, PrimeField, Zero};
+use kzg_ceremony_crypto::{G1, G2, Powers, Transcript, Witness};
+use crate::parallel::prelude::*;
+use crate::points::{g1_from_bytes, g1_to_bytes, g2_from_bytes, g2_to_bytes, point_error};
+use crate::{Result, WrapperError};
+
+/// First bytes of every ptau file
+const MAGIC: [u8; 4] = *b"ptau";
+const VERSION: u32 = 1;
+const SECTION_HEADER: u32 = 1;
+const SECTION_TAU_G1: u32 = 2;
+const SECTION_TAU_G2: u32 = 3;
+const SECTION_CONTRIBUTIONS: u32 = 7;
+/// Bytes of a base field element
+const N8Q: usize = 48;
+const G1_SIZE: usize = 2 * N8Q;
+const G2_SIZE: usize = 4 * N8Q;
+
+/**
+ * Writes the powers of tau of a sub-ceremony as a snarkjs `.ptau` file: the header,
+ * tauG1, tauG2 and an empty contributions section.
+ *
+ * The power `p` of the file is the largest with `2^(p+1) - 1` G1 powers and `2^p` G2
+ * powers in the ceremony, and exactly that many are written, like 127 and 64 for 4096
+ * and 65 powers. alphaTauG1, betaTauG1 and betaG2 are absent, the KZG ceremony has no
+ * alpha and beta secrets
+ */
+pub fn export_ptau<W: Write>(transcript: &Transcript, mut writer: W) -> Result<()> {
+    let (g1_powers, g2_powers) = (&transcript.powers.g1, &transcript.powers.g2);
+    if g1_powers.is_empty() || g2_powers.is_empty() {
+        return Err(WrapperError::Params(String::from("there are no powers to export")));
+    }
+    let power = ptau_power(g1_powers.len(), g2_powers.len());
+    let (g1_powers, g2_powers) = (&g1_powers[..num_g1_powers(power)], &g2_powers[..num_g2_powers(power)]);
+
+    let tau_g1 = g1_powers
+        .par_iter()
+        .map(| point | Ok(g1_to_lem(&g1_from_bytes(point)?)))
+        .collect::<Result<Vec<_>>>()?;
+    let tau_g2 = g2_powers
+        .par_iter()
+        .map(| point | Ok(g2_to_lem(&g2_from_bytes(point)?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    writer.write_all(&MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&4u32.to_le_bytes())?;
+
+    write_section_header(&mut writer, SECTION_HEADER, 4 + N8Q + 4 + 4)?;
+    writer.write_all(&(N8Q as u32).to_le_bytes())?;
+    writer.write_all(&modulus().to_bytes_le())?;
+    writer.write_all(&power.to_le_bytes())?;
+    // ceremony power
+    writer.write_all(&power.to_le_bytes())?;
+
+    write_section_header(&mut writer, SECTION_TAU_G1, tau_g1.len() * G1_SIZE)?;
+    for point in &tau_g1 {
+        writer.write_all(point)?;
+    }
+    write_section_header(&mut writer, SECTION_TAU_G2, tau_g2.len() * G2_SIZE)?;
+    for point in &tau_g2 {
+        writer.write_all(point)?;
+    }
+    // the KZG contributions have no snarkjs representation
+    write_section_header(&mut writer, SECTION_CONTRIBUTIONS, 4)?;
+    writer.write_all(&0u32.to_le_bytes())?;
+
+    writer.flush()?;
+    Ok(())
+}
+
+/**
+ * Reads the tauG1 and tauG2 sections of a BLS12-381 `.ptau` file back into a transcript,
+ * to compare them with the ceremony. Their sizes must match the header power. The witness
+ * of the transcript is empty
+ */
+pub fn import_ptau<R: Read>(mut reader: R) -> Result<Transcript> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if magic != MAGIC {
+        return Err(ptau_error("not a ptau file"));
+    }
+    let version = read_u32(&mut reader)?;
+    if version != VERSION {
+        return Err(ptau_error(&format!("unsupported version {}", version)));
+    }
+
+    let (mut header, mut tau_g1, mut tau_g2) = (None, None, None);
+    for _ in 0..read_u32(&mut reader)? {
+        let section = read_u32(&mut reader)?;
+        let mut size = [0u8; 8];
+        reader.read_exact(&mut size)?;
+        let size = u64::from_le_bytes(size);
+        let target = match section {
+            SECTION_HEADER => &mut header,
+            SECTION_TAU_G1 => &mut tau_g1,
+            SECTION_TAU_G2 => &mut tau_g2,
+            _ => {
+                io::copy(&mut (&mut reader).take(size), &mut io::sink())?;
+                continue;
+            },
+        };
+        let mut content = Vec::new();
+        (&mut reader).take(size).read_to_end(&mut content)?;
+        if content.len() as u64 != size {
+            return Err(ptau_error("unexpected end of file"));
+        }
+        *target = Some(content);
+    }
+
+    let header = header.ok_or_else(|| ptau_error("missing header section"))?;
+    let n8q = header.get(..4).map(| bytes | u32::from_le_bytes(bytes.try_into().unwrap()));
+    if n8q != Some(N8Q as u32) || header.get(4..4 + N8Q) != Some(&modulus().to_bytes_le()[..]) {
+        return Err(ptau_error("only BLS12-381 ptau files are supported"));
+    }
+    let power = header
+        .get(4 + N8Q..4 + N8Q + 4)
+        .map(| bytes | u32::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or_else(|| ptau_error("missing power in the header"))?;
+    let tau_g1 = tau_g1.ok_or_else(|| ptau_error("missing tauG1 section"))?;
+    let tau_g2 = tau_g2.ok_or_else(|| ptau_error("missing tauG2 section"))?;
+    if power >= usize::BITS - 1
+        || tau_g1.len() != num_g1_powers(power) * G1_SIZE
+        || tau_g2.len() != num_g2_powers(power) * G2_SIZE
+    {
+        return Err(ptau_error(&format!("tauG1 and tauG2 do not hold the powers of a power {} file", power)));
+    }
+
+    let g1 = tau_g1
+        .par_chunks(G1_SIZE)
+        .map(| bytes | Ok(G1(g1_to_bytes(&g1_from_lem(bytes)?))))
+        .collect::<Result<Vec<_>>>()?;
+    let g2 = tau_g2
+        .par_chunks(G2_SIZE)
+        .map(| bytes | Ok(G2(g2_to_bytes(&g2_from_lem(bytes)?))))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Transcript {
+        powers: Powers { g1, g2 },
+        witness: Witness { products: Vec::new(), pubkeys: Vec::new(), signatures: Vec::new() },
+    })
+}
+
+/**
+ * Util functions
+ */
+fn ptau_error(message: &str) -> WrapperError {
+    WrapperError::Binary(message.to_string())
+}
+
+/// Largest `p` with `2^(p+1) - 1` G1 powers and `2^p` G2 powers available, both counts are positive
+fn ptau_power(num_g1: usize, num_g2: usize) -> u32 {
+    // floor(log2(num_g1 + 1)) - 1 and floor(log2(num_g2))
+    let g1_power = usize::BITS - (num_g1 + 1).leading_zeros() - 2;
+    let g2_power = usize::BITS - num_g2.leading_zeros() - 1;
+    g1_power.min(g2_power)
+}
+
+fn num_g1_powers(power: u32) -> usize {
+    (1 << (power + 1)) - 1
+}
+
+fn num_g2_powers(power: u32) -> usize {
+    1 << power
+}
+
+fn modulus() -> BigInteger384 {
+    <<Fq as PrimeField>::Params as FpParameters>::MODULUS
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn write_section_header<W: Write>(writer: &mut W, section: u32, size: usize) -> Result<()> {
+    writer.write_all(&section.to_le_bytes())?;
+    writer.write_all(&(size as u64).to_le_bytes())?;
+    Ok(())
+}
+
+/// snarkjs keeps field elements little-endian in Montgomery form, like arkworks does internally
+fn fq_to_lem(fq: &Fq) -> Vec<u8> {
+    fq.0.to_bytes_le()
+}
+
+fn fq_from_lem(bytes: &[u8]) -> Result<Fq> {
+    let mut limbs = [0u64; 6];
+    for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks(8)) {
+        *limb = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+    let repr = BigInteger384(limbs);
+    if repr >= modulus() {
+        return Err(point_error("coordinate is not in the field"));
+    }
+    Ok(Fq::new(repr))
+}
+
+/// Uncompressed `x | y`, all zeros for the point at infinity
+fn g1_to_lem(point: &G1Affine) -> Vec<u8> {
+    if point.is_zero() {
+        return vec![0; G1_SIZE];
+    }
+    [fq_to_lem(&point.x), fq_to_lem(&point.y)].concat()
+}
+
+fn g2_to_lem(point: &G2Affine) -> Vec<u8> {
+    if point.is_zero() {
+        return vec![0; G2_SIZE];
+    }
+    [
+        fq_to_lem(&point.x.c0),
+        fq_to_lem(&point.x.c1),
+        fq_to_lem(&point.y.c0),
+        fq_to_lem(&point.y.c1),
+    ].concat()
+}
+
+fn g1_from_lem(bytes: &[u8]) -> Result<G1Affine> {
+    if bytes.iter().all(| byte | *byte == 0) {
+        return Ok(G1Affine::zero());
+    }
+    let point = G1Affine::new(fq_from_lem(&bytes[..N8Q])?, fq_from_lem(&bytes[N8Q..])?, false);
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(point_error("G1 point is not in the subgroup"));
+    }
+    Ok(point)
+}
+
+fn g2_from_lem(bytes: &[u8]) -> Result<G2Affine> {
+    if bytes.iter().all(| byte | *byte == 0) {
+        return Ok(G2Affine::zero());
+    }
+    let x = Fq2::new(fq_from_lem(&bytes[..N8Q])?, fq_from_lem(&bytes[N8Q..2 * N8Q])?);
+    let y = Fq2::new(fq_from_lem(&bytes[2 * N8Q..3 * N8Q])?, fq_from_lem(&bytes[3 * N8Q..])?);
+    let point = G2Affine::new(x, y, false);
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(point_error("G2 point is not in the subgroup"));
+    }
+    Ok(point)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr;
+    use ark_ec::{AffineCurve, ProjectiveCurve};
+
+    #[test]
+    fn ptau_round_trip_test() {
+        let g1 = G1Affine::prime_subgroup_generator();
+        let g2 = G2Affine::prime_subgroup_generator();
+        let g1_powers: Vec<G1> = (1..=8u64)
+            .map(| i | G1(g1_to_bytes(&g1.mul(Fr::from(i).into_repr()).into_affine())))
+            .collect();
+        let g2_powers: Vec<G2> = (1..=3u64)
+            .map(| i | G2(g2_to_bytes(&g2.mul(Fr::from(i).into_repr()).into_affine())))
+            .collect();
+        let transcript = Transcript {
+            powers: Powers { g1: g1_powers, g2: g2_powers },
+            witness: Witness { products: Vec::new(), pubkeys: Vec::new(), signatures: Vec::new() },
+        };
+
+        let mut ptau = Vec::new();
+        export_ptau(&transcript, &mut ptau).unwrap();
+        assert_eq!(&ptau[..4], b"ptau");
+        // 2^1 G2 powers fit in the 3 available, so do the 2^2 - 1 G1 powers
+        let power_offset = 12 + 12 + 4 + N8Q;
+        assert_eq!(&ptau[power_offset..power_offset + 8], &[1u32.to_le_bytes(), 1u32.to_le_bytes()].concat()[..]);
+        assert_eq!(ptau.len(), 12 + (12 + 60) + (12 + 3 * G1_SIZE) + (12 + 2 * G2_SIZE) + (12 + 4));
+
+        let imported = import_ptau(&ptau[..]).unwrap();
+        assert_eq!(imported.powers.g1, transcript.powers.g1[..3]);
+        assert_eq!(imported.powers.g2, transcript.powers.g2[..2]);
+
+        // 127 and 64 of the 4096 and 65 powers of the Ethereum ceremony
+        assert_eq!(ptau_power(4096, 65), 6);
+
+        // sections that do not match the header power are rejected
+        let mut other_power = ptau.clone();
+        other_power[power_offset] = 2;
+        assert!(matches!(import_ptau(&other_power[..]), Err(WrapperError::Binary(_))));
+
+        // other curves are rejected
+        let mut other_curve = ptau.clone();
+        other_curve[12 + 12 + 4] ^= 1;
+        assert!(matches!(import_ptau(&other_curve[..]), Err(WrapperError::Binary(_))));
+        // so are points out of the subgroup
+        let mut corrupted = ptau;
+        corrupted[12 + 72 + 12 + 5] ^= 1;
+        assert!(matches!(import_ptau(&corrupted[..]), Err(WrapperError::Point(_))));
+    }
+}
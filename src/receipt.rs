@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+use kzg_ceremony_crypto::{G2, Identity};
+use crate::eth::{address_from_str, address_to_string, eip191_digest, recover_address};
+use crate::{Result, WrapperError};
+
+/**
+ * What the sequencer signs once a contribution is accepted: the participant
+ * identity and the potPubkey of each sub-ceremony
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Receipt {
+    pub identity: Identity,
+    pub witness: Vec<G2>,
+}
+
+/**
+ * Checks that `receipt` was signed with `personal_sign` by the sequencer account
+ * and that it binds `expected_identity` to `expected_pubkeys`
+ */
+pub fn verify_receipt(
+    receipt: &str,
+    signature: &str,
+    sequencer_address: &str,
+    expected_identity: &Identity,
+    expected_pubkeys: &[G2],
+) -> Result<Receipt> {
+    let sequencer_address = address_from_str(&sequencer_address.to_lowercase())?;
+    let signer = recover_address(&eip191_digest(receipt.as_bytes()), signature)?;
+    if signer != sequencer_address {
+        return Err(WrapperError::Receipt(format!(
+            "signed by {}, not by the sequencer {}",
+            address_to_string(&signer),
+            address_to_string(&sequencer_address),
+        )));
+    }
+
+    let parsed = serde_json::from_str::<Receipt>(receipt)?;
+    if parsed.identity != *expected_identity {
+        return Err(WrapperError::Receipt(format!("the receipt is for {}, not {}", parsed.identity, expected_identity)));
+    }
+    if parsed.witness != expected_pubkeys {
+        return Err(WrapperError::Receipt(String::from("the potPubkeys differ from the expected ones")));
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k256::ecdsa::SigningKey;
+    use kzg_ceremony_crypto::{DefaultEngine, Secret, get_pot_pubkeys};
+    use crate::eth::{address_of, sign_digest};
+
+    #[test]
+    fn verify_receipt_test() {
+        let sequencer_key = SigningKey::from_bytes(&[7; 32]).unwrap();
+        let sequencer_address = address_to_string(&address_of(&sequencer_key.verifying_key()));
+        let pubkeys = get_pot_pubkeys::<DefaultEngine>(&Secret::from([1; 32]));
+        let identity: Identity = "eth|0x000000000000000000000000000000000000dead".parse().unwrap();
+        let receipt = serde_json::to_string(&Receipt {
+            identity: identity.clone(),
+            witness: pubkeys.clone(),
+        }).unwrap();
+        let signature = sign_digest(&sequencer_key, &eip191_digest(receipt.as_bytes()));
+
+        let verified = verify_receipt(&receipt, &signature, &sequencer_address.to_uppercase().replace("0X", "0x"), &identity, &pubkeys).unwrap();
+        assert_eq!(verified.identity.to_string(), "eth|0x000000000000000000000000000000000000dead");
+
+        // another signer, other pubkeys, another identity or another receipt are rejected
+        let other_key = SigningKey::from_bytes(&[8; 32]).unwrap();
+        let forged = sign_digest(&other_key, &eip191_digest(receipt.as_bytes()));
+        assert!(matches!(verify_receipt(&receipt, &forged, &sequencer_address, &identity, &pubkeys), Err(WrapperError::Receipt(_))));
+        let other_pubkeys = get_pot_pubkeys::<DefaultEngine>(&Secret::from([2; 32]));
+        assert!(matches!(verify_receipt(&receipt, &signature, &sequencer_address, &identity, &other_pubkeys), Err(WrapperError::Receipt(_))));
+        let other_identity: Identity = "git|1|alice".parse().unwrap();
+        let result = verify_receipt(&receipt, &signature, &sequencer_address, &other_identity, &pubkeys);
+        assert!(matches!(result, Err(WrapperError::Receipt(message)) if message.contains("git|1|alice")));
+        let other_receipt = receipt.replace("dead", "beef");
+        assert!(matches!(verify_receipt(&other_receipt, &signature, &sequencer_address, &identity, &pubkeys), Err(WrapperError::Receipt(_))));
+    }
+}
@@ -0,0 +1,411 @@
+use std::fmt;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use kzg_ceremony_crypto::{BatchContribution, BatchTranscript, CeremonyError, Engine, Powers, Transcript};
+use crate::batch::first_failing_pubkey;
+use crate::progress::{Stage, Tracker};
+use crate::{CancelToken, CeremonyParams, EcdsaSignaturesReport, NoProgress, Progress, Result, WitnessCheck};
+
+/// Points checked between two progress updates, per thread
+const CHUNK_SIZE: usize = 1024;
+
+/// Outcome of one check
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum CheckOutcome {
+    Passed,
+    Failed { error: String },
+}
+
+impl CheckOutcome {
+    pub fn is_passed(&self) -> bool {
+        matches!(self, CheckOutcome::Passed)
+    }
+
+    pub(crate) fn from_result(result: Result<(), CeremonyError>) -> Self {
+        match result {
+            Ok(()) => CheckOutcome::Passed,
+            Err(error) => CheckOutcome::Failed { error: error.to_string() },
+        }
+    }
+}
+
+/// Milliseconds spent in each check
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Timings {
+    pub subgroup_ms: f64,
+    pub pairing_ms: f64,
+    pub witness_ms: f64,
+}
+
+/**
+ * Checks of one sub-ceremony
+ */
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubCeremonyReport {
+    pub index: usize,
+    pub num_g1_powers: usize,
+    pub num_g2_powers: usize,
+    /// False when the shape differs from the ceremony params
+    pub expected_shape: bool,
+    /// Every point is in the prime order subgroup
+    pub subgroup_check: CheckOutcome,
+    /// The powers are consecutive powers of the same tau in both groups
+    pub pairing_check: CheckOutcome,
+    /// Contributions in the witness, without the genesis entry. `None` for a contribution
+    pub num_participants: Option<usize>,
+    /// First witness entry whose running product does not follow from the previous
+    /// one and its pubkey, or the last one when it does not match the powers
+    pub first_failing_witness_index: Option<usize>,
+    pub timings: Timings,
+    /// Sha256 of the compact JSON of the sub-ceremony
+    pub transcript_hash: String,
+}
+
+impl SubCeremonyReport {
+    pub fn is_valid(&self) -> bool {
+        self.expected_shape
+            && self.subgroup_check.is_passed()
+            && self.pairing_check.is_passed()
+            && self.first_failing_witness_index.is_none()
+    }
+}
+
+/**
+ * Detailed outcome of verifying a batch transcript or contribution,
+ * serializable for audit pipelines
+ */
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationReport {
+    pub is_valid: bool,
+    /// Error that stopped the verification before the sub-ceremonies, like a wrong number of them
+    pub error: Option<String>,
+    pub sub_ceremonies: Vec<SubCeremonyReport>,
+    /// Sha256 of the compact JSON of the whole batch
+    pub transcript_hash: String,
+    pub elapsed_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ecdsa_signatures: Option<EcdsaSignaturesReport>,
+}
+
+impl VerificationReport {
+    /// Adds the optional ECDSA audit, a mismatch makes the report invalid
+    pub fn with_ecdsa_signatures(mut self, report: EcdsaSignaturesReport) -> Self {
+        self.is_valid &= report.is_valid();
+        self.ecdsa_signatures = Some(report);
+        self
+    }
+
+    fn new(sub_ceremonies: Vec<SubCeremonyReport>, expected: usize, transcript_hash: String, start: f64) -> Self {
+        let error = (sub_ceremonies.len() != expected)
+            .then(|| CeremonyError::UnexpectedNumContributions(expected, sub_ceremonies.len()).to_string());
+        Self {
+            is_valid: error.is_none() && sub_ceremonies.iter().all(SubCeremonyReport::is_valid),
+            error,
+            sub_ceremonies,
+            transcript_hash,
+            elapsed_ms: now_ms() - start,
+            ecdsa_signatures: None,
+        }
+    }
+}
+
+/**
+ * Verifies every sub-ceremony of a transcript: shape, subgroup, pairings and witness chain
+ */
+pub fn verify_transcript_report<E: Engine>(
+    batch_transcript: &BatchTranscript,
+    params: &CeremonyParams,
+    witness_check: WitnessCheck,
+) -> VerificationReport {
+    verify_transcript_report_with_progress::<E>(batch_transcript, params, witness_check, &NoProgress, &CancelToken::new())
+        .expect("a fresh token is never cancelled")
+}
+
+/**
+ * Like `verify_transcript_report`, reporting between chunks of points and stopping
+ * with `WrapperError::Cancelled` once `cancel` is set
+ */
+pub fn verify_transcript_report_with_progress<E: Engine>(
+    batch_transcript: &BatchTranscript,
+    params: &CeremonyParams,
+    witness_check: WitnessCheck,
+    progress: &dyn Progress,
+    cancel: &CancelToken,
+) -> Result<VerificationReport> {
+    let start = now_ms();
+    let transcripts = &batch_transcript.transcripts;
+    let total_points = transcripts.iter().map(| transcript | num_points(&transcript.powers, Some(transcript))).sum();
+    let tracker = Tracker::new(progress, cancel, transcripts.len(), total_points);
+    // one sub-ceremony at a time, the chunks of each of them already use every thread
+    let sub_ceremonies = transcripts
+        .iter()
+        .enumerate()
+        .map(| (index, transcript) | sub_ceremony_report::<E>(index, &transcript.powers, Some(transcript), params, witness_check, &tracker))
+        .collect::<Result<_>>()?;
+    Ok(VerificationReport::new(sub_ceremonies, params.sizes().len(), hash(batch_transcript), start))
+}
+
+/**
+ * Verifies every sub-ceremony of a contribution: shape, subgroup and pairings
+ */
+pub fn verify_contribution_report<E: Engine>(contribution: &BatchContribution, params: &CeremonyParams) -> VerificationReport {
+    let start = now_ms();
+    let cancel = CancelToken::new();
+    let tracker = Tracker::new(&NoProgress, &cancel, contribution.contributions.len(), 0);
+    let sub_ceremonies = contribution
+        .contributions
+        .iter()
+        .enumerate()
+        .map(| (index, contribution) | sub_ceremony_report::<E>(index, &contribution.powers, None, params, WitnessCheck::default(), &tracker))
+        .collect::<Result<_>>()
+        .expect("a fresh token is never cancelled");
+    VerificationReport::new(sub_ceremonies, params.sizes().len(), hash(contribution), start)
+}
+
+fn sub_ceremony_report<E: Engine>(
+    index: usize,
+    powers: &Powers,
+    transcript: Option<&Transcript>,
+    params: &CeremonyParams,
+    witness_check: WitnessCheck,
+    tracker: &Tracker,
+) -> Result<SubCeremonyReport> {
+    let (g1, g2) = (&powers.g1, &powers.g2);
+    let mut timings = Timings::default();
+
+    let start = now_ms();
+    let subgroup_check = match tracker.run_chunked(Stage::Subgroup, index, 0..g1.len(), CHUNK_SIZE, | chunk | {
+        E::validate_g1(&g1[chunk.clone()]).err().map(| error | offset_power(error, chunk.start))
+    })? {
+        Some(error) => Some(error),
+        None => tracker.run_chunked(Stage::Subgroup, index, 0..g2.len(), CHUNK_SIZE, | chunk | {
+            E::validate_g2(&g2[chunk.clone()]).err().map(| error | offset_power(error, chunk.start))
+        })?,
+    };
+    let subgroup_check = CheckOutcome::from_result(subgroup_check.map_or(Ok(()), Err));
+    timings.subgroup_ms = now_ms() - start;
+
+    let start = now_ms();
+    let pairing_check = if g2.len() < 2 || g1.len() < g2.len() {
+        CheckOutcome::Failed { error: String::from("not enough powers to check") }
+    } else {
+        // the chunks overlap by one power, so every consecutive pair is checked
+        let g1_failure = tracker.run_chunked(Stage::Pairing, index, 0..g1.len() - 1, CHUNK_SIZE, | chunk | {
+            E::verify_g1(&g1[chunk.start..chunk.end + 1], g2[1]).err()
+        })?;
+        let result = match g1_failure {
+            Some(error) => Err(error),
+            None => {
+                let result = E::verify_g2(&g1[..g2.len()], g2);
+                tracker.advance(Stage::Pairing, index, g2.len())?;
+                result
+            },
+        };
+        CheckOutcome::from_result(result)
+    };
+    timings.pairing_ms = now_ms() - start;
+
+    let start = now_ms();
+    let first_failing_witness_index = match transcript {
+        Some(transcript) => first_failing_witness_index::<E>(index, transcript, witness_check, tracker)?,
+        None => None,
+    };
+    timings.witness_ms = now_ms() - start;
+
+    Ok(SubCeremonyReport {
+        index,
+        num_g1_powers: g1.len(),
+        num_g2_powers: g2.len(),
+        expected_shape: params.sizes().get(index) == Some(&(g1.len(), g2.len())),
+        subgroup_check,
+        pairing_check,
+        num_participants: transcript.map(| transcript | transcript.witness.pubkeys.len().saturating_sub(1)),
+        first_failing_witness_index,
+        timings,
+        transcript_hash: match transcript {
+            Some(transcript) => hash(transcript),
+            None => hash(powers),
+        },
+    })
+}
+
+fn first_failing_witness_index<E: Engine>(
+    index: usize,
+    transcript: &Transcript,
+    witness_check: WitnessCheck,
+    tracker: &Tracker,
+) -> Result<Option<usize>> {
+    let (products, pubkeys) = (&transcript.witness.products, &transcript.witness.pubkeys);
+    if products.is_empty() || products.len() != pubkeys.len() {
+        return Ok(Some(0));
+    }
+    let broken = tracker.run_chunked(Stage::Witness, index, 1..products.len(), CHUNK_SIZE, | chunk | {
+        first_failing_pubkey::<E>(products, pubkeys, chunk, witness_check)
+    })?;
+    if broken.is_some() {
+        return Ok(broken);
+    }
+    // the chain should end at the current powers
+    Ok(match transcript.powers.g1.get(1) {
+        Some(tau) if products.len() > 1 && products.last() != Some(tau) => Some(products.len() - 1),
+        _ => None,
+    })
+}
+
+/**
+ * Util functions
+ */
+/// Points, and witness entries, the checks of a sub-ceremony go through
+fn num_points(powers: &Powers, transcript: Option<&Transcript>) -> usize {
+    let witness = transcript.map_or(0, | transcript | transcript.witness.products.len().saturating_sub(1));
+    // the g1 pairings go through the consecutive pairs
+    (2 * (powers.g1.len() + powers.g2.len())).saturating_sub(1) + witness
+}
+
+/// Errors of a chunk point at the whole powers
+fn offset_power(error: CeremonyError, offset: usize) -> CeremonyError {
+    match error {
+        CeremonyError::InvalidG1Power(index, error) => CeremonyError::InvalidG1Power(index + offset, error),
+        CeremonyError::InvalidG2Power(index, error) => CeremonyError::InvalidG2Power(index + offset, error),
+        error => error,
+    }
+}
+
+fn hash<T: Serialize>(value: &T) -> String {
+    let mut hasher = Sha256::new();
+    // writing into the hasher never fails
+    serde_json::to_writer(HashWriter(&mut hasher), value).unwrap();
+    hex::encode(hasher.finalize())
+}
+
+struct HashWriter<'a>(&'a mut Sha256);
+
+impl std::io::Write for HashWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// `Instant` panics in browsers, they have their own clock
+#[cfg(target_family = "wasm")]
+fn now_ms() -> f64 {
+    js_sys::Date::now()
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn now_ms() -> f64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0.0, | elapsed | elapsed.as_secs_f64() * 1000.0)
+}
+
+impl fmt::Display for CheckOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckOutcome::Passed => write!(f, "passed"),
+            CheckOutcome::Failed { error } => write!(f, "FAILED ({})", error),
+        }
+    }
+}
+
+impl fmt::Display for VerificationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Verification is correct: {}", self.is_valid)?;
+        if let Some(error) = &self.error {
+            writeln!(f, "Error: {}", error)?;
+        }
+        writeln!(f, "Hash: {}", self.transcript_hash)?;
+        for sub_ceremony in &self.sub_ceremonies {
+            writeln!(
+                f,
+                "Sub-ceremony {}: {} G1 and {} G2 powers{}",
+                sub_ceremony.index,
+                sub_ceremony.num_g1_powers,
+                sub_ceremony.num_g2_powers,
+                if sub_ceremony.expected_shape { "" } else { " (unexpected shape)" },
+            )?;
+            writeln!(f, "  subgroup check: {} in {:.0} ms", sub_ceremony.subgroup_check, sub_ceremony.timings.subgroup_ms)?;
+            writeln!(f, "  pairing check: {} in {:.0} ms", sub_ceremony.pairing_check, sub_ceremony.timings.pairing_ms)?;
+            if let Some(num_participants) = sub_ceremony.num_participants {
+                let witness = match sub_ceremony.first_failing_witness_index {
+                    Some(index) => format!("FAILED at index {}", index),
+                    None => String::from("passed"),
+                };
+                writeln!(f, "  witness of {} participants: {} in {:.0} ms", num_participants, witness, sub_ceremony.timings.witness_ms)?;
+            }
+            writeln!(f, "  hash: {}", sub_ceremony.transcript_hash)?;
+        }
+        if let Some(report) = &self.ecdsa_signatures {
+            writeln!(
+                f,
+                "ECDSA signatures: {} valid, {} absent, {} mismatches",
+                report.valid.len(), report.absent.len(), report.mismatches.len(),
+            )?;
+            for mismatch in &report.mismatches {
+                let signer = mismatch.signer.as_deref().unwrap_or("a malformed signature");
+                writeln!(f, "  index {}: {} signed by {}", mismatch.witness_index, mismatch.identity, signer)?;
+            }
+        }
+        write!(f, "Elapsed: {:.0} ms", self.elapsed_ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kzg_ceremony_crypto::{DefaultEngine, G1, Identity, Secret};
+
+    #[test]
+    fn verification_report_test() {
+        let params = CeremonyParams::new(vec![(4, 2), (8, 3)]).unwrap();
+        let mut batch_transcript = BatchTranscript::new(params.sizes());
+        for seed in 1..=2u8 {
+            let identity: Identity = "git|1|alice".parse().unwrap();
+            let mut contribution = batch_transcript.contribution();
+            contribution.add_entropy::<DefaultEngine>(&Secret::from([seed; 32]), &identity).unwrap();
+            for (transcript, contribution) in batch_transcript.transcripts.iter_mut().zip(contribution.contributions) {
+                transcript.witness.products.push(contribution.powers.g1[1]);
+                transcript.witness.pubkeys.push(contribution.pot_pubkey);
+                transcript.witness.signatures.push(contribution.bls_signature);
+                transcript.powers = contribution.powers;
+            }
+            batch_transcript.participant_ids.push(identity);
+        }
+
+        let report = verify_transcript_report::<DefaultEngine>(&batch_transcript, &params, WitnessCheck::PerEntry);
+        assert!(report.is_valid, "{}", report);
+        assert_eq!(report.sub_ceremonies[1].num_g1_powers, 8);
+        assert_eq!(report.sub_ceremonies[1].num_participants, Some(2));
+        assert_eq!(report.transcript_hash, hash(&batch_transcript));
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["subCeremonies"][0]["pairingCheck"]["status"], "passed");
+
+        // a broken witness chain is located, broken powers fail the pairing check
+        let mut broken = batch_transcript.clone();
+        broken.transcripts[0].witness.products[1] = G1::one();
+        broken.transcripts[1].powers.g1.swap(2, 3);
+        let report = verify_transcript_report::<DefaultEngine>(&broken, &params, WitnessCheck::Batched);
+        assert!(!report.is_valid);
+        assert_eq!(report.sub_ceremonies[0].first_failing_witness_index, Some(1));
+        assert!(report.sub_ceremonies[0].pairing_check.is_passed());
+        assert!(!report.sub_ceremonies[1].pairing_check.is_passed());
+        assert_ne!(report.transcript_hash, hash(&batch_transcript));
+
+        // contributions have no witness, and the shape comes from the params
+        let contribution = batch_transcript.contribution();
+        let other_params = CeremonyParams::new(vec![(4, 2), (16, 3)]).unwrap();
+        let report = verify_contribution_report::<DefaultEngine>(&contribution, &other_params);
+        assert!(!report.is_valid && !report.sub_ceremonies[1].expected_shape);
+        assert_eq!(report.sub_ceremonies[0].num_participants, None);
+        let report = verify_contribution_report::<DefaultEngine>(&contribution, &CeremonyParams::new(vec![(4, 2)]).unwrap());
+        assert!(report.error.is_some());
+    }
+}
@@ -0,0 +1,328 @@
+use std::io::Read;
+use std::thread;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use kzg_ceremony_crypto::BatchTranscript;
+use crate::{
+    CeremonyParams,
+    EngineKind,
+    Result,
+    WrapperError,
+    contribute_with_string,
+    read_batch_transcript,
+    verify_update_with_string,
+};
+
+/// Errors of `lobby/try_contribute` meaning the participant should ask again later
+const RETRY_CODES: [&str; 2] = [
+    "TryContributeError::AnotherContributionInProgress",
+    "TryContributeError::RateLimited",
+];
+
+/**
+ * Where the sequencer is and how long to wait for it
+ */
+#[derive(Clone, Debug)]
+pub struct SequencerConfig {
+    /// Like `https://seq.ceremony.ethereum.org`
+    pub base_url: String,
+    /// Timeout of connecting and of every read or write, not of the whole request,
+    /// so downloading the full transcript can take longer
+    pub request_timeout: Duration,
+    /// Time between two `lobby/try_contribute` requests
+    pub poll_interval: Duration,
+    /// Give up waiting in the lobby after this long, `None` waits forever
+    pub lobby_timeout: Option<Duration>,
+    /// Engine computing and checking our contribution
+    pub engine: EngineKind,
+}
+
+impl SequencerConfig {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            request_timeout: Duration::from_secs(60),
+            poll_interval: Duration::from_secs(10),
+            lobby_timeout: None,
+            engine: EngineKind::default(),
+        }
+    }
+}
+
+/// Sign in links returned by `auth/request_link`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthLinks {
+    pub eth_auth_url: String,
+    pub github_auth_url: String,
+}
+
+/// Identity providers of the sequencer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuthProvider {
+    Ethereum,
+    Github,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdToken {
+    /// Participant identity, like "eth|0x..." or "git|<id>|<username>"
+    pub sub: String,
+    pub nickname: String,
+    pub provider: String,
+    pub exp: u64,
+}
+
+/// Returned by the auth callback, the session id authenticates the other requests
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthSession {
+    pub id_token: IdToken,
+    pub session_id: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SequencerStatus {
+    pub lobby_size: usize,
+    pub num_contributions: usize,
+    pub sequencer_address: String,
+}
+
+/// Returned by `contribute`: the receipt JSON and its signature by the sequencer
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContributionReceipt {
+    pub receipt: String,
+    pub signature: String,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    code: String,
+    #[serde(default)]
+    error: String,
+}
+
+/**
+ * Client of the KZG ceremony sequencer HTTP API
+ */
+pub struct SequencerClient {
+    config: SequencerConfig,
+    agent: ureq::Agent,
+}
+
+impl SequencerClient {
+    pub fn new(config: SequencerConfig) -> Self {
+        let agent = ureq::AgentBuilder::new()
+            .timeout_connect(config.request_timeout)
+            .timeout_read(config.request_timeout)
+            .timeout_write(config.request_timeout)
+            .build();
+        Self { config, agent }
+    }
+
+    pub fn status(&self) -> Result<SequencerStatus> {
+        let body = self.request(self.agent.get(&self.url("info/status")), None)?;
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    /// Downloads the current transcript, parsed as it arrives
+    pub fn current_state(&self) -> Result<BatchTranscript> {
+        let response = self.agent
+            .get(&self.url("info/current_state"))
+            .call()
+            .map_err(into_sequencer_error)?;
+        read_batch_transcript(response.into_reader())
+    }
+
+    /// Links to sign in with an Ethereum address or a GitHub account.
+    /// With `redirect_to`, the sequencer sends the session there after the sign in
+    pub fn request_link(&self, redirect_to: Option<&str>) -> Result<AuthLinks> {
+        let mut request = self.agent.get(&self.url("auth/request_link"));
+        if let Some(redirect_to) = redirect_to {
+            request = request.query("redirect_to", redirect_to);
+        }
+        let body = self.request(request, None)?;
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    /// Exchanges the `code` and `state` the provider sent back for a session
+    pub fn auth_callback(&self, provider: AuthProvider, code: &str, state: &str) -> Result<AuthSession> {
+        let path = match provider {
+            AuthProvider::Ethereum => "auth/callback/eth",
+            AuthProvider::Github => "auth/callback/github",
+        };
+        let request = self.agent
+            .get(&self.url(path))
+            .query("code", code)
+            .query("state", state);
+        let body = self.request(request, None)?;
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    /// Asks for the current contribution. Returns `None` when it is not our turn yet
+    pub fn try_contribute(&self, session_id: &str) -> Result<Option<String>> {
+        let request = self.agent
+            .post(&self.url("lobby/try_contribute"))
+            .set("Authorization", &bearer(session_id));
+        let (status, body) = send(request, Some("{}"))?;
+        if let Ok(error) = serde_json::from_str::<ErrorResponse>(&body) {
+            if RETRY_CODES.contains(&error.code.as_str()) {
+                return Ok(None);
+            }
+            return Err(WrapperError::Sequencer(format!("{}: {}", error.code, error.error)));
+        }
+        if status != 200 {
+            return Err(WrapperError::Sequencer(format!("unexpected status {}: {}", status, body)));
+        }
+        Ok(Some(body))
+    }
+
+    /// Polls the lobby until it is our turn, returns the contribution to update
+    pub fn wait_for_turn(&self, session_id: &str) -> Result<String> {
+        let start = Instant::now();
+        loop {
+            if let Some(contribution) = self.try_contribute(session_id)? {
+                return Ok(contribution);
+            }
+            if matches!(self.config.lobby_timeout, Some(timeout) if start.elapsed() >= timeout) {
+                return Err(WrapperError::Sequencer(String::from("timed out waiting in the lobby")));
+            }
+            thread::sleep(self.config.poll_interval);
+        }
+    }
+
+    /// Uploads the updated contribution
+    pub fn contribute(&self, session_id: &str, contribution: &str) -> Result<ContributionReceipt> {
+        let request = self.agent
+            .post(&self.url("contribute"))
+            .set("Authorization", &bearer(session_id));
+        let body = self.request(request, Some(contribution))?;
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    /// Gives our turn back, so the next participant doesn't wait for the timeout
+    pub fn abort_contribution(&self, session_id: &str) -> Result<()> {
+        let request = self.agent
+            .post(&self.url("contribute/abort"))
+            .set("Authorization", &bearer(session_id));
+        self.request(request, Some("{}"))?;
+        Ok(())
+    }
+
+    /**
+     * The whole flow: waits for our turn, adds our entropy, checks the update
+     * and uploads it. Our turn is aborted when the update can't be computed
+     */
+    pub fn participate(
+        &self,
+        session_id: &str,
+        string_secret: &str,
+        string_identity: &str,
+        params: &CeremonyParams,
+    ) -> Result<ContributionReceipt> {
+        let contribution = self.wait_for_turn(session_id)?;
+        let engine = self.config.engine;
+        let updated = contribute_with_string(contribution.clone(), string_secret, string_identity, params, engine)
+            .and_then(| updated | {
+                verify_update_with_string(contribution, updated.clone(), engine)?;
+                Ok(updated)
+            });
+        match updated {
+            Ok(updated) => self.contribute(session_id, &updated),
+            Err(error) => {
+                let _ = self.abort_contribution(session_id);
+                Err(error)
+            },
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.config.base_url, path)
+    }
+
+    /// Sends a request, any status but a success is an error
+    fn request(&self, request: ureq::Request, body: Option<&str>) -> Result<String> {
+        let (status, body) = send(request, body)?;
+        if !(200..300).contains(&status) {
+            let message = match serde_json::from_str::<ErrorResponse>(&body) {
+                Ok(error) => format!("{}: {}", error.code, error.error),
+                Err(_) => format!("unexpected status {}: {}", status, body),
+            };
+            return Err(WrapperError::Sequencer(message));
+        }
+        Ok(body)
+    }
+}
+
+/**
+ * Util functions
+ */
+fn bearer(session_id: &str) -> String {
+    format!("Bearer {}", session_id)
+}
+
+/// Returns the status and the body of the response, also for error statuses
+fn send(request: ureq::Request, body: Option<&str>) -> Result<(u16, String)> {
+    let result = match body {
+        Some(body) => request.set("Content-Type", "application/json").send_string(body),
+        None => request.call(),
+    };
+    let response = match result {
+        Ok(response) => response,
+        Err(ureq::Error::Status(_, response)) => response,
+        Err(error) => return Err(WrapperError::Sequencer(error.to_string())),
+    };
+    let status = response.status();
+    // read without the size limit of `into_string`, contributions are large
+    let mut body = String::new();
+    response.into_reader().read_to_string(&mut body)?;
+    Ok((status, body))
+}
+
+fn into_sequencer_error(error: ureq::Error) -> WrapperError {
+    WrapperError::Sequencer(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kzg_ceremony_crypto::DefaultEngine;
+    use crate::mock_sequencer::MockSequencer;
+
+    #[test]
+    fn sequencer_client_test() {
+        let params = CeremonyParams::new(vec![(4, 2), (8, 3)]).unwrap();
+        let sequencer = MockSequencer::start(&params).unwrap();
+        let mut config = SequencerConfig::new(sequencer.url());
+        config.poll_interval = Duration::from_millis(10);
+        let client = SequencerClient::new(config);
+
+        let alice = client.auth_callback(AuthProvider::Ethereum, "0x000000000000000000000000000000000000dead", "mock").unwrap();
+        let bob = client.auth_callback(AuthProvider::Github, "bob", "mock").unwrap();
+        assert_eq!(client.status().unwrap().lobby_size, 2);
+
+        let result = client.try_contribute("unknown");
+        assert!(matches!(result, Err(WrapperError::Sequencer(message)) if message.contains("UnknownSessionId")));
+
+        // bob keeps polling the lobby until alice gives the turn back
+        assert!(client.try_contribute(&alice.session_id).unwrap().is_some());
+        let aborting = {
+            let client = SequencerClient::new(SequencerConfig::new(sequencer.url()));
+            let session_id = alice.session_id.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(50));
+                client.abort_contribution(&session_id)
+            })
+        };
+        let receipt = client.participate(
+            &bob.session_id,
+            "6b86b273ff34fce19d6b804eff5a3f5747ada4eaa22f1d49c01e52ddb7875b4b",
+            &bob.id_token.sub,
+            &params,
+        ).unwrap();
+        aborting.join().unwrap().unwrap();
+        assert!(receipt.signature.starts_with("0x"));
+
+        let transcript = sequencer.transcript();
+        assert_eq!(transcript.participant_ids.len(), 2);
+        assert!(transcript.verify_self::<DefaultEngine>(params.sizes().to_vec()).is_ok());
+    }
+}
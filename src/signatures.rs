@@ -0,0 +1,119 @@
+use serde::Serialize;
+use kzg_ceremony_crypto::{BatchTranscript, Engine};
+use crate::parallel::prelude::*;
+use crate::{Result, WrapperError};
+
+/// State of the BLS signature of a contribution in one sub-ceremony
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SignatureStatus {
+    Valid,
+    Invalid,
+    /// The participant did not sign their identity
+    Absent,
+}
+
+/**
+ * BLS signatures of one participant over their identity, one per sub-ceremony
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlsSignatureReport {
+    pub identity: String,
+    /// Index of the contribution in the witness of every transcript
+    pub witness_index: usize,
+    pub signatures: Vec<SignatureStatus>,
+}
+
+impl BlsSignatureReport {
+    /// True when the contribution is bound to the identity in every sub-ceremony
+    pub fn is_identity_bound(&self) -> bool {
+        self.signatures.iter().all(| status | *status == SignatureStatus::Valid)
+    }
+
+    pub fn has_invalid(&self) -> bool {
+        self.signatures.contains(&SignatureStatus::Invalid)
+    }
+}
+
+/**
+ * Checks the BLS signature of every contribution over its `participant_ids` entry,
+ * made with the tau committed in the matching potPubkey
+ */
+pub fn verify_bls_signatures<E: Engine>(batch_transcript: &BatchTranscript) -> Result<Vec<BlsSignatureReport>> {
+    let num_participants = batch_transcript.participant_ids.len();
+    for (index, transcript) in batch_transcript.transcripts.iter().enumerate() {
+        let witness = &transcript.witness;
+        if witness.signatures.len() != num_participants || witness.pubkeys.len() != num_participants {
+            return Err(WrapperError::Witness(format!(
+                "sub-ceremony {} has {} signatures and {} pubkeys for {} participants",
+                index, witness.signatures.len(), witness.pubkeys.len(), num_participants,
+            )));
+        }
+    }
+
+    let reports = batch_transcript
+        .participant_ids
+        .par_iter()
+        .enumerate()
+        .map(| (witness_index, identity) | {
+            let message = identity.to_string();
+            let signatures = batch_transcript
+                .transcripts
+                .par_iter()
+                .map(| transcript | {
+                    let pubkey = transcript.witness.pubkeys[witness_index];
+                    match transcript.witness.signatures[witness_index].0 {
+                        None => SignatureStatus::Absent,
+                        Some(signature) if E::verify_signature(signature, message.as_bytes(), pubkey) => SignatureStatus::Valid,
+                        Some(_) => SignatureStatus::Invalid,
+                    }
+                })
+                .collect();
+            BlsSignatureReport { identity: message, witness_index, signatures }
+        })
+        .collect();
+    Ok(reports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kzg_ceremony_crypto::{BlsSignature, DefaultEngine, Identity, Secret};
+
+    #[test]
+    fn verify_bls_signatures_test() {
+        let sizes = [(4, 2), (8, 3)];
+        let mut batch_transcript = BatchTranscript::new(&sizes);
+        for (seed, identity) in [(1u8, "eth|0x000000000000000000000000000000000000dead"), (2, "git|1|alice")] {
+            let identity: Identity = identity.parse().unwrap();
+            let mut contribution = batch_transcript.contribution();
+            contribution.add_entropy::<DefaultEngine>(&Secret::from([seed; 32]), &identity).unwrap();
+            for (transcript, contribution) in batch_transcript.transcripts.iter_mut().zip(contribution.contributions) {
+                transcript.witness.products.push(contribution.powers.g1[1]);
+                transcript.witness.pubkeys.push(contribution.pot_pubkey);
+                transcript.witness.signatures.push(contribution.bls_signature);
+                transcript.powers = contribution.powers;
+            }
+            batch_transcript.participant_ids.push(identity);
+        }
+
+        let reports = verify_bls_signatures::<DefaultEngine>(&batch_transcript).unwrap();
+        assert_eq!(reports.len(), 3);
+        // the genesis entry has no signature
+        assert_eq!(reports[0].signatures, vec![SignatureStatus::Absent; 2]);
+        assert!(reports[1].is_identity_bound() && reports[2].is_identity_bound());
+
+        // a signature over another identity is invalid, a removed one is absent
+        batch_transcript.participant_ids.swap(1, 2);
+        batch_transcript.transcripts[1].witness.signatures[1] = BlsSignature(None);
+        let reports = verify_bls_signatures::<DefaultEngine>(&batch_transcript).unwrap();
+        assert_eq!(reports[1].identity, "git|1|alice");
+        assert_eq!(reports[1].signatures, vec![SignatureStatus::Invalid, SignatureStatus::Absent]);
+        assert!(reports[2].has_invalid());
+
+        batch_transcript.transcripts[0].witness.signatures.pop();
+        let result = verify_bls_signatures::<DefaultEngine>(&batch_transcript);
+        assert!(matches!(result, Err(WrapperError::Witness(_))));
+    }
+}
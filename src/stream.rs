@@ -0,0 +1,209 @@
+use std::io::{BufReader, Read};
+use serde::de::DeserializeOwned;
+use kzg_ceremony_crypto::{BatchContribution, BatchTranscript, Transcript};
+use crate::Result;
+
+/// Bytes buffered between the reader and the JSON parser
+const BUFFER_SIZE: usize = 1 << 20;
+
+/**
+ * Deserializes a batch transcript straight from a reader. The JSON text is never
+ * held in memory, points and witness entries are parsed as their bytes arrive.
+ * The parsed transcript is held whole, it is about half the size of the JSON
+ */
+pub fn read_batch_transcript<R: Read>(reader: R) -> Result<BatchTranscript> {
+    read_json(reader)
+}
+
+/**
+ * Deserializes a batch contribution straight from a reader
+ */
+pub fn read_batch_contribution<R: Read>(reader: R) -> Result<BatchContribution> {
+    read_json(reader)
+}
+
+/**
+ * Parses a batch transcript from chunks of JSON pushed as they arrive, like the chunks
+ * of a `fetch` body in the browser, where no blocking `Read` is available. Each
+ * sub-ceremony is deserialized as soon as its JSON is complete, so the JSON of only
+ * one sub-ceremony is buffered at a time. The parsed transcript is held whole
+ */
+#[derive(Default)]
+pub struct TranscriptParser {
+    /// The JSON without the sub-ceremonies, like `{"transcripts":[],"participantIds":[...]}`
+    skeleton: Vec<u8>,
+    /// JSON of the sub-ceremony being received
+    current: Vec<u8>,
+    transcripts: Vec<Transcript>,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    /// Last string of the top-level object, the key of the next array
+    last_string: Vec<u8>,
+    in_transcripts: bool,
+}
+
+impl TranscriptParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, chunk: &[u8]) -> Result<()> {
+        chunk.iter().try_for_each(| byte | self.push_byte(*byte))
+    }
+
+    /// Fails if the JSON is truncated or malformed
+    pub fn finish(self) -> Result<BatchTranscript> {
+        let mut batch_transcript: BatchTranscript = serde_json::from_slice(&self.skeleton)?;
+        batch_transcript.transcripts = self.transcripts;
+        Ok(batch_transcript)
+    }
+
+    fn push_byte(&mut self, byte: u8) -> Result<()> {
+        if self.in_string {
+            self.write(byte);
+            if self.escaped {
+                self.escaped = false;
+            } else if byte == b'\\' {
+                self.escaped = true;
+            } else if byte == b'"' {
+                self.in_string = false;
+            } else if self.depth == 1 {
+                self.last_string.push(byte);
+            }
+            return Ok(());
+        }
+        // between the sub-ceremonies, only separators are expected
+        if self.in_transcripts && self.depth == 2 && !matches!(byte, b'{' | b']' | b',' | b' ' | b'\t' | b'\n' | b'\r') {
+            return Err(json_error("every sub-ceremony should be an object"));
+        }
+        match byte {
+            b'"' => {
+                if self.depth == 1 {
+                    self.last_string.clear();
+                }
+                self.in_string = true;
+                self.write(byte);
+            },
+            b'[' if self.depth == 1 && self.last_string == b"transcripts" => {
+                self.skeleton.push(byte);
+                self.depth += 1;
+                self.in_transcripts = true;
+            },
+            b'{' | b'[' => {
+                self.depth += 1;
+                self.write(byte);
+            },
+            b']' if self.in_transcripts && self.depth == 2 => {
+                self.skeleton.push(byte);
+                self.depth -= 1;
+                self.in_transcripts = false;
+            },
+            b'}' | b']' => {
+                self.write(byte);
+                self.depth = self.depth.saturating_sub(1);
+                if self.in_transcripts && self.depth == 2 {
+                    self.transcripts.push(serde_json::from_slice(&self.current)?);
+                    self.current.clear();
+                }
+            },
+            _ => self.write(byte),
+        }
+        Ok(())
+    }
+
+    /// The bytes of a sub-ceremony go to `current`, the separators between them are dropped
+    fn write(&mut self, byte: u8) {
+        if !self.in_transcripts {
+            self.skeleton.push(byte);
+        } else if self.depth > 2 {
+            self.current.push(byte);
+        }
+    }
+}
+
+/**
+ * Util functions
+ */
+fn json_error(message: &str) -> crate::WrapperError {
+    <serde_json::Error as serde::de::Error>::custom(message).into()
+}
+
+fn read_json<T: DeserializeOwned, R: Read>(reader: R) -> Result<T> {
+    let reader = BufReader::with_capacity(BUFFER_SIZE, reader);
+    let value = serde_json::from_reader(reader)?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kzg_ceremony_crypto::{G1, G2};
+    use crate::WrapperError;
+
+    /// Returns at most a few bytes per read, like a slow network stream
+    struct ChunkedReader<'a> {
+        bytes: &'a [u8],
+    }
+
+    impl Read for ChunkedReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = buf.len().min(self.bytes.len()).min(7);
+            buf[..len].copy_from_slice(&self.bytes[..len]);
+            self.bytes = &self.bytes[len..];
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn read_batch_transcript_test() {
+        let json = serde_json::json!({
+            "transcripts": [{
+                "numG1Powers": 4,
+                "numG2Powers": 2,
+                "powersOfTau": {
+                    "G1Powers": vec![G1::one(); 4],
+                    "G2Powers": vec![G2::one(); 2],
+                },
+                "witness": {
+                    "runningProducts": vec![G1::one(); 3],
+                    "potPubkeys": vec![G2::one(); 3],
+                    "blsSignatures": vec![""; 3],
+                },
+            }],
+            "participantIds": vec!["eth|0x000000000000000000000000000000000000dead"; 3],
+            "participantEcdsaSignatures": vec![""; 3],
+        }).to_string();
+
+        let expected = serde_json::from_str::<BatchTranscript>(&json).unwrap();
+        let streamed = read_batch_transcript(ChunkedReader { bytes: json.as_bytes() }).unwrap();
+        assert_eq!(
+            serde_json::to_value(&streamed).unwrap(),
+            serde_json::to_value(&expected).unwrap(),
+        );
+        assert_eq!(streamed.transcripts[0].witness.products.len(), 3);
+
+        // truncated and trailing inputs are rejected
+        let truncated = &json.as_bytes()[..json.len() / 2];
+        assert!(matches!(read_batch_transcript(truncated), Err(WrapperError::Json(_))));
+        let trailing = format!("{} {{}}", json);
+        assert!(matches!(read_batch_transcript(trailing.as_bytes()), Err(WrapperError::Json(_))));
+
+        // pushed in chunks, with more sub-ceremonies and whitespace
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let transcript = value["transcripts"][0].clone();
+        value["transcripts"].as_array_mut().unwrap().push(transcript);
+        let pretty = serde_json::to_string_pretty(&value).unwrap();
+        let mut parser = TranscriptParser::new();
+        pretty.as_bytes().chunks(7).try_for_each(| chunk | parser.push(chunk)).unwrap();
+        let pushed = parser.finish().unwrap();
+        assert_eq!(pushed.transcripts.len(), 2);
+        assert_eq!(serde_json::to_value(&pushed).unwrap(), serde_json::to_value(read_batch_transcript(pretty.as_bytes()).unwrap()).unwrap());
+
+        let mut parser = TranscriptParser::new();
+        parser.push(truncated).unwrap();
+        assert!(matches!(parser.finish(), Err(WrapperError::Json(_))));
+        let mut parser = TranscriptParser::new();
+        assert!(matches!(parser.push(br#"{"transcripts": [1]}"#), Err(WrapperError::Json(_))));
+    }
+}
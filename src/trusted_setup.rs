@@ -0,0 +1,132 @@
+use std::iter::successors;
+use ark_bls12_381::{Fr, G1Affine, G1Projective};
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::{FftField, Field, One, PrimeField};
+use kzg_ceremony_crypto::BatchTranscript;
+use crate::parallel::prelude::*;
+use crate::points::{g1_from_bytes, g1_to_bytes, g2_from_bytes, g2_to_bytes};
+use crate::{Result, WrapperError};
+
+/**
+ * Builds the c-kzg `trusted_setup.txt` of a sub-ceremony: the numbers of G1 and G2
+ * points, then the G1 points in Lagrange form and the G2 points in monomial form,
+ * one compressed point in hex per line. The transcript should be verified first
+ */
+pub fn export_trusted_setup(batch_transcript: &BatchTranscript, sub_ceremony_index: usize) -> Result<String> {
+    let transcript = batch_transcript
+        .transcripts
+        .get(sub_ceremony_index)
+        .ok_or_else(|| WrapperError::Params(format!(
+            "no sub-ceremony {}, the transcript has {}", sub_ceremony_index, batch_transcript.transcripts.len(),
+        )))?;
+    let (g1_powers, g2_powers) = (&transcript.powers.g1, &transcript.powers.g2);
+    if !g1_powers.len().is_power_of_two() {
+        return Err(WrapperError::Params(format!(
+            "the number of G1 powers should be a power of two, found {}", g1_powers.len(),
+        )));
+    }
+
+    let monomial = g1_powers
+        .par_iter()
+        .map(g1_from_bytes)
+        .collect::<Result<Vec<_>>>()?;
+    let lagrange = lagrange_from_monomial(&monomial);
+
+    let mut lines = vec![g1_powers.len().to_string(), g2_powers.len().to_string()];
+    lines.extend(lagrange.iter().map(| point | hex::encode(g1_to_bytes(point))));
+    for point in g2_powers {
+        lines.push(hex::encode(g2_to_bytes(&g2_from_bytes(point)?)));
+    }
+    Ok(lines.join("\n") + "\n")
+}
+
+/// Inverse FFT of the powers `[tau^i]G1` over the roots of unity of the scalar field,
+/// which gives the Lagrange basis `[L_i(tau)]G1`
+fn lagrange_from_monomial(powers: &[G1Affine]) -> Vec<G1Affine> {
+    let n = powers.len();
+    let mut points: Vec<G1Projective> = powers.iter().map(| point | point.into_projective()).collect();
+    if n < 2 {
+        return G1Projective::batch_normalization_into_affine(&points);
+    }
+    // n is a power of two, below the 2-adicity of the scalar field
+    let omega_inv = Fr::get_root_of_unity(n).and_then(| omega | omega.inverse()).unwrap();
+
+    let log_n = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - log_n);
+        if i < j {
+            points.swap(i, j);
+        }
+    }
+
+    let mut half = 1;
+    while half < n {
+        let step = omega_inv.pow([(n / (2 * half)) as u64]);
+        let twiddles: Vec<Fr> = successors(Some(Fr::one()), | w | Some(*w * step)).take(half).collect();
+        points.par_chunks_mut(2 * half).for_each(| chunk | {
+            let (left, right) = chunk.split_at_mut(half);
+            left.par_iter_mut()
+                .zip(right.par_iter_mut())
+                .zip(twiddles.par_iter())
+                .for_each(| ((a, b), w) | {
+                    let t = b.mul(w.into_repr());
+                    *b = *a - t;
+                    *a += t;
+                });
+        });
+        half *= 2;
+    }
+
+    let n_inv = Fr::from(n as u64).inverse().unwrap().into_repr();
+    points.par_iter_mut().for_each(| point | *point = point.mul(n_inv));
+    G1Projective::batch_normalization_into_affine(&points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::G2Affine;
+    use kzg_ceremony_crypto::{G1, G2};
+
+    #[test]
+    fn export_trusted_setup_test() {
+        let (n, tau) = (8, Fr::from(5u64));
+        let powers: Vec<Fr> = successors(Some(Fr::one()), | p | Some(*p * tau)).take(n).collect();
+        let g1 = G1Affine::prime_subgroup_generator();
+        let g2 = G2Affine::prime_subgroup_generator();
+        let g1_powers: Vec<G1> = powers.iter().map(| p | G1(g1_to_bytes(&g1.mul(p.into_repr()).into_affine()))).collect();
+        let g2_powers: Vec<G2> = powers[..3].iter().map(| p | G2(g2_to_bytes(&g2.mul(p.into_repr()).into_affine()))).collect();
+        let json = serde_json::json!({
+            "transcripts": [{
+                "numG1Powers": n,
+                "numG2Powers": 3,
+                "powersOfTau": { "G1Powers": g1_powers, "G2Powers": g2_powers },
+                "witness": { "runningProducts": [], "potPubkeys": [], "blsSignatures": [] },
+            }],
+            "participantIds": [],
+            "participantEcdsaSignatures": [],
+        });
+        let batch_transcript: BatchTranscript = serde_json::from_value(json).unwrap();
+
+        let setup = export_trusted_setup(&batch_transcript, 0).unwrap();
+        let lines: Vec<&str> = setup.lines().collect();
+        assert_eq!(lines.len(), 2 + n + 3);
+        assert_eq!(&lines[..2], &["8", "3"]);
+        assert_eq!(lines[2 + n], hex::encode(G2::one().0));
+
+        // L_i(tau) = w^i (tau^n - 1) / (n (tau - w^i))
+        let omega = Fr::get_root_of_unity(n).unwrap();
+        let vanishing = tau.pow([n as u64]) - Fr::one();
+        for (i, line) in lines[2..2 + n].iter().enumerate() {
+            let w = omega.pow([i as u64]);
+            let l_i = w * vanishing / (Fr::from(n as u64) * (tau - w));
+            let expected = g1_to_bytes(&g1.mul(l_i.into_repr()).into_affine());
+            assert_eq!(*line, hex::encode(expected));
+        }
+
+        assert!(matches!(export_trusted_setup(&batch_transcript, 1), Err(WrapperError::Params(_))));
+        let mut corrupted = batch_transcript;
+        corrupted.transcripts[0].powers.g1[3].0[47] ^= 1;
+        assert!(matches!(export_trusted_setup(&corrupted, 0), Err(WrapperError::Point(_))));
+    }
+}
@@ -0,0 +1,98 @@
+use kzg_ceremony_crypto::{
+    G2,
+    BatchContribution,
+    CeremonyError,
+    Engine,
+};
+use crate::{Result, WrapperError};
+
+/// Verifies that `updated` is `previous` with one more secret tau added,
+/// the one committed in the `potPubkey` of each sub-ceremony
+pub fn verify_update<E: Engine>(previous: &BatchContribution, updated: &BatchContribution) -> Result<()> {
+    if previous.contributions.len() != updated.contributions.len() {
+        return Err(CeremonyError::UnexpectedNumContributions(
+            previous.contributions.len(),
+            updated.contributions.len(),
+        ).into());
+    }
+
+    previous
+        .contributions
+        .iter()
+        .zip(updated.contributions.iter())
+        .enumerate()
+        .try_for_each(| (index, (previous, updated)) | {
+            let (num_g1, num_g2) = (previous.powers.g1.len(), previous.powers.g2.len());
+            let result = if updated.powers.g1.len() != num_g1 || num_g1 < 2 {
+                Err(CeremonyError::UnexpectedNumG1Powers(num_g1, updated.powers.g1.len()))
+            } else if updated.powers.g2.len() != num_g2 || num_g2 < 2 || num_g2 > num_g1 {
+                Err(CeremonyError::UnexpectedNumG2Powers(num_g2, updated.powers.g2.len()))
+            } else if updated.pot_pubkey == G2::zero() {
+                Err(CeremonyError::ZeroPubkey)
+            } else {
+                E::validate_g1(&updated.powers.g1)
+                .and_then(|_| E::validate_g2(&updated.powers.g2))
+                // Pairing check: new & previous first powers, contributor pubkey
+                .and_then(|_| E::verify_pubkey(
+                    updated.powers.g1[1],
+                    previous.powers.g1[1],
+                    updated.pot_pubkey,
+                ))
+                // Pairing checks: powers are consecutive in both groups
+                .and_then(|_| E::verify_g1(&updated.powers.g1, updated.powers.g2[1]))
+                .and_then(|_| E::verify_g2(&updated.powers.g1[..num_g2], &updated.powers.g2))
+            };
+            result.map_err(| source | WrapperError::SubCeremony { index, source })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kzg_ceremony_crypto::{DefaultEngine, Identity, Secret, G1};
+
+    fn initial_contribution(sizes: &[(usize, usize)]) -> BatchContribution {
+        let contributions: Vec<_> = sizes
+            .iter()
+            .map(| (num_g1, num_g2) | serde_json::json!({
+                "numG1Powers": num_g1,
+                "numG2Powers": num_g2,
+                "powersOfTau": {
+                    "G1Powers": vec![G1::one(); *num_g1],
+                    "G2Powers": vec![G2::one(); *num_g2],
+                },
+                "potPubkey": G2::one(),
+                "blsSignature": "",
+            }))
+            .collect();
+        let json = serde_json::json!({ "contributions": contributions, "ecdsaSignature": "" });
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn verify_update_test() {
+        let identity = Identity::eth_from_str("0x000000000000000000000000000000000000dead").unwrap();
+        let previous = initial_contribution(&[(4, 2), (8, 3)]);
+        let mut updated = previous.clone();
+        updated.add_entropy::<DefaultEngine>(&Secret::new([1; 32]), &identity).unwrap();
+        assert!(verify_update::<DefaultEngine>(&previous, &updated).is_ok());
+
+        // A second update is checked against the first one
+        let mut second = updated.clone();
+        second.add_entropy::<DefaultEngine>(&Secret::new([2; 32]), &identity).unwrap();
+        assert!(verify_update::<DefaultEngine>(&updated, &second).is_ok());
+        assert!(verify_update::<DefaultEngine>(&previous, &second).is_err());
+
+        // Corrupted powers are reported with their sub-ceremony
+        let mut corrupted = updated.clone();
+        corrupted.contributions[1].powers.g1.swap(2, 3);
+        let result = verify_update::<DefaultEngine>(&previous, &corrupted);
+        assert!(matches!(result, Err(WrapperError::SubCeremony { index: 1, .. })));
+
+        // A pubkey that does not match the added tau
+        let mut corrupted = updated.clone();
+        corrupted.contributions[0].pot_pubkey = second.contributions[0].pot_pubkey;
+        let result = verify_update::<DefaultEngine>(&previous, &corrupted);
+        assert!(matches!(result, Err(WrapperError::SubCeremony { index: 0, source: CeremonyError::PubKeyPairingFailed })));
+    }
+}
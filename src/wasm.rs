@@ -1,45 +1,408 @@
+#[cfg(feature = "console_error_panic_hook")]
 use std::panic;
-use js_sys::Promise;
-use wasm_bindgen::prelude::{wasm_bindgen, JsValue};
+use js_sys::{Function, Promise, Reflect};
+use wasm_bindgen::prelude::{wasm_bindgen, JsError, JsValue};
+#[cfg(feature = "parallel")]
 use wasm_bindgen_rayon::init_thread_pool;
-use kzg_ceremony_crypto::{Transcript, CeremonyError};
 use crate::{
+    CancelToken,
+    CeremonyParams,
+    EngineKind,
+    WitnessCheck,
+    EntropyMixer,
+    KdfParams,
+    MouseSample,
+    Progress,
+    ProgressUpdate,
+    TranscriptParser,
+    WrapperError,
     get_pot_pubkeys_with_string,
     check_subgroup_with_string,
+    check_subgroup_report_with_string,
+    cross_check_with_reader,
     contribute_with_string,
-    verify_with_string,
+    contribute_with_progress,
+    verify_update_with_string,
+    verify_batch_transcript,
+    verify_with_reader,
+    verify_report_with_reader,
+    verify_report_with_progress,
+    report_batch_transcript,
+    verify_inclusion_with_reader,
+    verify_bls_signatures_with_reader,
+    verify_ecdsa_signatures_with_reader,
+    verify_receipt_with_string,
+    pot_pubkeys_typed_data_with_string,
+    add_ecdsa_signature_with_string,
+    verify_ecdsa_signature_with_string,
 };
 
+/// Starts the thread pool. The single-threaded package keeps it,
+/// so the same JS code drives both packages
 #[wasm_bindgen]
 pub fn init_threads(n: usize) -> Promise {
+    // the hook is optional, like in a build with only the `arkworks` feature
+    #[cfg(feature = "console_error_panic_hook")]
     panic::set_hook(Box::new(console_error_panic_hook::hook));
-    init_thread_pool(n)
+    start_thread_pool(n)
+}
+
+/// False for the single-threaded package, built without the `parallel` feature
+#[wasm_bindgen]
+pub fn is_parallel_wasm() -> bool {
+    cfg!(feature = "parallel")
 }
 
+/// Mixes the typed text, the `[{ x, y, time }, ...]` mouse samples
+/// and the browser randomness into the hex secret taken by the other functions.
+/// With `kdf`, like `{ memoryKib, iterations, parallelism }` or `{}` for the defaults,
+/// the text is hardened with Argon2id
 #[wasm_bindgen]
-pub fn contribute_wasm(input: &str, string_secret: &str, string_identity: &str) -> JsValue {
+pub fn mix_entropy_wasm(text: &str, mouse_samples: JsValue, kdf: JsValue) -> Result<String, JsError> {
+    let mouse_samples: Vec<MouseSample> = if mouse_samples.is_undefined() || mouse_samples.is_null() {
+        Vec::new()
+    } else {
+        serde_wasm_bindgen::from_value(mouse_samples)
+        .map_err(|error| js_error("InvalidEntropy", &error.to_string(), None))?
+    };
+    let mut mixer = EntropyMixer::new();
+    mixer
+        .add_mouse_samples(&mouse_samples)
+        .add_os_randomness()
+        .map_err(to_js_error)?;
+    if kdf.is_undefined() || kdf.is_null() {
+        mixer.add_text(text);
+    } else {
+        let params: KdfParams = serde_wasm_bindgen::from_value(kdf)
+        .map_err(|error| js_error("InvalidParams", &error.to_string(), None))?;
+        mixer.add_passphrase(text, &params).map_err(to_js_error)?;
+    }
+    mixer.finalize_to_string().map_err(to_js_error)
+}
+
+#[wasm_bindgen]
+pub fn contribute_wasm(
+    input: &str,
+    string_secret: &str,
+    string_identity: &str,
+    params: JsValue,
+    engine: Option<String>,
+) -> Result<JsValue, JsError> {
+    let params = to_params(params)?;
     let contribution = contribute_with_string(
         input.to_string(),
         string_secret,
         string_identity,
-    ).unwrap();
-    return serde_wasm_bindgen::to_value(&contribution).unwrap();
+        &params,
+        to_engine(engine)?,
+    ).map_err(to_js_error)?;
+    to_js_value(&contribution)
+}
+
+/// Like `contribute_wasm`, calling `progress({ stage, subCeremony, numSubCeremonies,
+/// pointsProcessed, totalPoints, percentage })` before and after adding the secret, which
+/// is a single computation. Returning `false` from the first call aborts with a `Cancelled` error
+#[wasm_bindgen]
+pub fn contribute_with_progress_wasm(
+    input: &str,
+    string_secret: &str,
+    string_identity: &str,
+    params: JsValue,
+    engine: Option<String>,
+    progress: Function,
+) -> Result<JsValue, JsError> {
+    let params = to_params(params)?;
+    let progress = JsProgress::new(progress);
+    let contribution = contribute_with_progress(
+        input.to_string(),
+        string_secret,
+        string_identity,
+        &params,
+        to_engine(engine)?,
+        &progress,
+        &progress.cancel,
+    ).map_err(to_js_error)?;
+    to_js_value(&contribution)
+}
+
+/// Throws if `updated` is not a correct update of `previous`,
+/// the error `subCeremony` tells which sub-ceremony failed
+#[wasm_bindgen]
+pub fn verify_update_wasm(previous: &str, updated: &str, engine: Option<String>) -> Result<(), JsError> {
+    verify_update_with_string(previous.to_string(), updated.to_string(), to_engine(engine)?).map_err(to_js_error)
+}
+
+/// Throws the reason the contribution is invalid, `subgroup_check_report_wasm` lists them all
+#[wasm_bindgen]
+pub fn subgroup_check_wasm(input: &str, params: JsValue, engine: Option<String>) -> Result<(), JsError> {
+    let params = to_params(params)?;
+    check_subgroup_with_string(input.to_string(), &params, to_engine(engine)?).map_err(to_js_error)
+}
+
+/// Returns the `VerificationReport` of the contribution, without witness checks
+#[wasm_bindgen]
+pub fn subgroup_check_report_wasm(input: &str, params: JsValue, engine: Option<String>) -> Result<JsValue, JsError> {
+    let params = to_params(params)?;
+    let report = check_subgroup_report_with_string(input.to_string(), &params, to_engine(engine)?).map_err(to_js_error)?;
+    to_js_value(&report)
+}
+
+#[wasm_bindgen]
+pub fn get_pot_pubkeys_wasm(string_secret: &str, engine: Option<String>) -> Result<JsValue, JsError> {
+    let pot_pubkeys = get_pot_pubkeys_with_string(string_secret, to_engine(engine)?).map_err(to_js_error)?;
+    to_js_value(&pot_pubkeys)
+}
+
+/// Throws the first reason the transcript is invalid, `verify_report_wasm` lists them all
+#[wasm_bindgen]
+pub fn verify_wasm(transcript: &str, params: JsValue, engine: Option<String>) -> Result<(), JsError> {
+    let params = to_params(params)?;
+    // parse in place, a copy of the transcript would double the memory
+    verify_with_reader(transcript.as_bytes(), &params, to_engine(engine)?).map_err(to_js_error)
+}
+
+/// Returns `{ isValid, error, subCeremonies, transcriptHash, elapsedMs }` with the
+/// shape, checks, participants and timings of each sub-ceremony. With `batched`,
+/// the witness chain is checked with randomized multi-pairings
+#[wasm_bindgen]
+pub fn verify_report_wasm(
+    transcript: &str,
+    params: JsValue,
+    engine: Option<String>,
+    batched: Option<bool>,
+) -> Result<JsValue, JsError> {
+    let params = to_params(params)?;
+    let report = verify_report_with_reader(transcript.as_bytes(), &params, to_engine(engine)?, to_witness_check(batched))
+        .map_err(to_js_error)?;
+    to_js_value(&report)
+}
+
+/// Like `verify_report_wasm`, calling `progress(update)` between chunks of points,
+/// see `contribute_with_progress_wasm`. Returning `false` from it aborts with a `Cancelled` error
+#[wasm_bindgen]
+pub fn verify_report_with_progress_wasm(
+    transcript: &str,
+    params: JsValue,
+    engine: Option<String>,
+    batched: Option<bool>,
+    progress: Function,
+) -> Result<JsValue, JsError> {
+    let params = to_params(params)?;
+    let progress = JsProgress::new(progress);
+    let report = verify_report_with_progress(
+        transcript.as_bytes(),
+        &params,
+        to_engine(engine)?,
+        to_witness_check(batched),
+        &progress,
+        &progress.cancel,
+    ).map_err(to_js_error)?;
+    to_js_value(&report)
+}
+
+/// Receives a transcript as `Uint8Array` chunks, like the chunks of a `fetch` body,
+/// so the JSON text is never held whole: only the JSON of the sub-ceremony being
+/// received is buffered. `verify` and `verifyReport` consume the stream
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct TranscriptStream {
+    parser: TranscriptParser,
+}
+
+#[wasm_bindgen]
+impl TranscriptStream {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, chunk: &[u8]) -> Result<(), JsError> {
+        self.parser.push(chunk).map_err(to_js_error)
+    }
+
+    /// Like `verify_wasm`
+    pub fn verify(self, params: JsValue, engine: Option<String>) -> Result<(), JsError> {
+        let params = to_params(params)?;
+        let batch_transcript = self.parser.finish().map_err(to_js_error)?;
+        verify_batch_transcript(&batch_transcript, &params, to_engine(engine)?).map_err(to_js_error)
+    }
+
+    /// Like `verify_report_wasm`
+    #[wasm_bindgen(js_name = verifyReport)]
+    pub fn verify_report(self, params: JsValue, engine: Option<String>, batched: Option<bool>) -> Result<JsValue, JsError> {
+        let params = to_params(params)?;
+        let batch_transcript = self.parser.finish().map_err(to_js_error)?;
+        let report = report_batch_transcript(&batch_transcript, &params, to_engine(engine)?, to_witness_check(batched));
+        to_js_value(&report)
+    }
+}
+
+/// Returns `{ engines, reports, divergences }`, the report of each engine and the checks
+/// they disagree on. Needs a package built with both engines
+#[wasm_bindgen]
+pub fn cross_check_wasm(transcript: &str, params: JsValue) -> Result<JsValue, JsError> {
+    let params = to_params(params)?;
+    let report = cross_check_with_reader(transcript.as_bytes(), &params).map_err(to_js_error)?;
+    to_js_value(&report)
 }
 
+/// Returns `{ status: "notFound" }` or `{ status: "found", reports: [...] }`
+/// with one report per contribution of the identity. With `batched`, the witness
+/// chain is checked with randomized multi-pairings
 #[wasm_bindgen]
-pub fn subgroup_check_wasm(input: &str) -> bool {
-    let result = check_subgroup_with_string(input.to_string()).unwrap();
-    return result;
+pub fn verify_inclusion_wasm(
+    transcript: &str,
+    string_identity: &str,
+    engine: Option<String>,
+    batched: Option<bool>,
+) -> Result<JsValue, JsError> {
+    let report = verify_inclusion_with_reader(transcript.as_bytes(), string_identity, to_engine(engine)?, to_witness_check(batched))
+        .map_err(to_js_error)?;
+    to_js_value(&report)
 }
 
+/// Returns one `{ identity, witnessIndex, signatures }` report per participant, with
+/// a `"valid"`, `"invalid"` or `"absent"` signature status per sub-ceremony
 #[wasm_bindgen]
-pub fn get_pot_pubkeys_wasm(string_secret: &str) -> JsValue {
-    let pot_pubkeys = get_pot_pubkeys_with_string(string_secret).unwrap();
-    return serde_wasm_bindgen::to_value(&pot_pubkeys).unwrap();
+pub fn verify_bls_signatures_wasm(transcript: &str, engine: Option<String>) -> Result<JsValue, JsError> {
+    let reports = verify_bls_signatures_with_reader(transcript.as_bytes(), to_engine(engine)?).map_err(to_js_error)?;
+    to_js_value(&reports)
 }
 
+/// Returns `{ valid, absent, mismatches }`, the witness indexes of the `eth|` participants
+/// by state of their potPubkeys signature, with `{ witnessIndex, identity, signer }` mismatches
 #[wasm_bindgen]
-pub fn verify_wasm(transcript: &str) -> bool {
-    let result = verify_with_string(transcript.to_string()).unwrap();
-    return result;
+pub fn verify_ecdsa_signatures_wasm(transcript: &str) -> Result<JsValue, JsError> {
+    let report = verify_ecdsa_signatures_with_reader(transcript.as_bytes()).map_err(to_js_error)?;
+    to_js_value(&report)
+}
+
+/// Returns the receipt `{ identity, witness }` once its signature by the sequencer,
+/// its identity and its potPubkeys, derived from the secret, are checked
+#[wasm_bindgen]
+pub fn verify_receipt_wasm(
+    receipt: &str,
+    signature: &str,
+    sequencer_address: &str,
+    string_secret: &str,
+    string_identity: &str,
+    params: JsValue,
+    engine: Option<String>,
+) -> Result<JsValue, JsError> {
+    let params = to_params(params)?;
+    let receipt = verify_receipt_with_string(receipt, signature, sequencer_address, string_secret, string_identity, &params, to_engine(engine)?)
+        .map_err(to_js_error)?;
+    to_js_value(&receipt)
+}
+
+/// EIP-712 typed data of the potPubkeys of the secret, to pass to `eth_signTypedData_v4`
+#[wasm_bindgen]
+pub fn pot_pubkeys_typed_data_wasm(string_secret: &str, params: JsValue, engine: Option<String>) -> Result<String, JsError> {
+    let params = to_params(params)?;
+    pot_pubkeys_typed_data_with_string(string_secret, &params, to_engine(engine)?).map_err(to_js_error)
+}
+
+/// Checks the wallet signature against the `eth|` identity and adds it to the contribution
+#[wasm_bindgen]
+pub fn add_ecdsa_signature_wasm(contribution: &str, signature: &str, string_identity: &str) -> Result<String, JsError> {
+    add_ecdsa_signature_with_string(contribution.to_string(), signature, string_identity).map_err(to_js_error)
+}
+
+#[wasm_bindgen]
+pub fn verify_ecdsa_signature_wasm(contribution: &str, string_identity: &str) -> Result<bool, JsError> {
+    verify_ecdsa_signature_with_string(contribution.to_string(), string_identity).map_err(to_js_error)
+}
+
+/// Names of the engines this package was built with, the first one is the default
+#[wasm_bindgen]
+pub fn engines_wasm() -> Result<JsValue, JsError> {
+    to_js_value(&EngineKind::available())
+}
+
+/**
+ * Util functions
+ */
+#[cfg(feature = "parallel")]
+fn start_thread_pool(n: usize) -> Promise {
+    init_thread_pool(n)
+}
+
+#[cfg(not(feature = "parallel"))]
+fn start_thread_pool(_n: usize) -> Promise {
+    Promise::resolve(&JsValue::UNDEFINED)
+}
+
+fn to_js_value<T: serde::Serialize + ?Sized>(value: &T) -> Result<JsValue, JsError> {
+    serde_wasm_bindgen::to_value(value)
+    .map_err(|error| js_error("SerializationError", &error.to_string(), None))
+}
+
+/// Ceremony shape given as `[[numG1Powers, numG2Powers], ...]`,
+/// defaults to the Ethereum ceremony when `undefined` or `null`
+fn to_params(params: JsValue) -> Result<CeremonyParams, JsError> {
+    if params.is_undefined() || params.is_null() {
+        return Ok(CeremonyParams::ethereum());
+    }
+    serde_wasm_bindgen::from_value(params)
+    .map_err(|error| js_error("InvalidParams", &error.to_string(), None))
+}
+
+/// `"blst"` or `"arkworks"`, defaults to the first engine of the build when `undefined`
+fn to_engine(engine: Option<String>) -> Result<EngineKind, JsError> {
+    match engine {
+        Some(engine) => engine.parse().map_err(to_js_error),
+        None => Ok(EngineKind::default()),
+    }
+}
+
+fn to_witness_check(batched: Option<bool>) -> WitnessCheck {
+    match batched {
+        Some(true) => WitnessCheck::Batched,
+        _ => WitnessCheck::PerEntry,
+    }
+}
+
+/// JS callback of the functions `*_with_progress_wasm`. The computation blocks the worker,
+/// so its message handler can't abort it: the callback aborts by returning `false`, or by
+/// throwing. This works the same in the threaded and the single-threaded packages
+struct JsProgress {
+    callback: Function,
+    cancel: CancelToken,
+}
+
+impl JsProgress {
+    fn new(callback: Function) -> Self {
+        Self { callback, cancel: CancelToken::new() }
+    }
+}
+
+impl Progress for JsProgress {
+    fn update(&self, update: &ProgressUpdate) {
+        let update = match serde_wasm_bindgen::to_value(update) {
+            Ok(update) => update,
+            Err(_) => return,
+        };
+        // returning `false`, or throwing, aborts
+        match self.callback.call1(&JsValue::NULL, &update) {
+            Ok(result) if result.as_bool() != Some(false) => {},
+            _ => self.cancel.cancel(),
+        }
+    }
+}
+
+fn to_js_error(error: WrapperError) -> JsError {
+    js_error(error.code(), &error.to_string(), error.sub_ceremony())
+}
+
+/// Builds a JS `Error` carrying a stable `code` and,
+/// when known, the index of the failing `subCeremony`
+fn js_error(code: &str, message: &str, sub_ceremony: Option<usize>) -> JsError {
+    let error = JsError::new(message);
+    // the clone points to the same JS object, so we can attach fields to it
+    let object = JsValue::from(error.clone());
+    let _ = Reflect::set(&object, &JsValue::from_str("code"), &JsValue::from_str(code));
+    if let Some(index) = sub_ceremony {
+        let _ = Reflect::set(&object, &JsValue::from_str("subCeremony"), &JsValue::from(index as u32));
+    }
+    error
 }
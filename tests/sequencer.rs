@@ -0,0 +1,67 @@
+//! Contributes with the CLI to a local mock sequencer, offline.
+//! Run with `cargo test --features mock-sequencer`
+
+#![cfg(feature = "mock-sequencer")]
+
+use std::fs;
+use std::process::Command;
+use wrapper_small_pot::{
+    AuthProvider,
+    CeremonyParams,
+    ContributionReceipt,
+    MockSequencer,
+    SequencerClient,
+    SequencerConfig,
+};
+
+const SECRET: &str = "6b86b273ff34fce19d6b804eff5a3f5747ada4eaa22f1d49c01e52ddb7875b4b";
+
+#[test]
+fn cli_contribute_test() {
+    let params = CeremonyParams::new(vec![(4, 2), (8, 3)]).unwrap();
+    let sequencer = MockSequencer::start(&params).unwrap();
+    let client = SequencerClient::new(SequencerConfig::new(sequencer.url()));
+    let session = client
+        .auth_callback(AuthProvider::Ethereum, "0x000000000000000000000000000000000000dead", "mock")
+        .unwrap();
+
+    let receipt_path = std::env::temp_dir().join(format!("receipt-{}.json", std::process::id()));
+    let output = Command::new(env!("CARGO_BIN_EXE_wrapper-small-pot"))
+        .args(["sequencer", "--url", sequencer.url(), "contribute"])
+        .args(["--session-id", &session.session_id, "--identity", &session.id_token.sub])
+        .args(["--secret", SECRET, "--params", "4x2,8x3", "--poll-interval", "0"])
+        .arg("--receipt")
+        .arg(&receipt_path)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let receipt: ContributionReceipt = serde_json::from_str(&fs::read_to_string(&receipt_path).unwrap()).unwrap();
+    assert!(receipt.receipt.contains(&session.id_token.sub));
+    let transcript = sequencer.transcript();
+    assert_eq!(transcript.participant_ids.len(), 2);
+    assert_eq!(transcript.participant_ids[1].to_string(), session.id_token.sub);
+
+    // the session ends with the contribution
+    let output = Command::new(env!("CARGO_BIN_EXE_wrapper-small-pot"))
+        .args(["sequencer", "--url", sequencer.url(), "contribute"])
+        .args(["--session-id", &session.session_id, "--identity", &session.id_token.sub])
+        .args(["--entropy", "--params", "4x2,8x3", "--poll-interval", "0"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(6));
+
+    // the receipt binds the identity to the pubkeys of the secret, not another identity or secret
+    let verify_receipt = | secret: &str, identity: &str | Command::new(env!("CARGO_BIN_EXE_wrapper-small-pot"))
+        .arg("verify-receipt")
+        .arg("--receipt")
+        .arg(&receipt_path)
+        .args(["--sequencer-address", &sequencer.sequencer_address(), "--secret", secret])
+        .args(["--identity", identity, "--params", "4x2,8x3"])
+        .output()
+        .unwrap();
+    assert!(verify_receipt(SECRET, &session.id_token.sub).status.success());
+    assert_eq!(verify_receipt(&SECRET.replace("6", "7"), &session.id_token.sub).status.code(), Some(1));
+    assert_eq!(verify_receipt(SECRET, "git|1|alice").status.code(), Some(1));
+    fs::remove_file(&receipt_path).unwrap();
+}
@@ -1,70 +1,78 @@
-import init, {
-    init_threads,
-    contribute_wasm,
-    subgroup_check_wasm,
-    get_pot_pubkeys_wasm,
-    verify_wasm,
-} from "./pkg/wrapper_small_pot.js";
+// threads need a SharedArrayBuffer, only available in cross-origin isolated pages
+const pkg = self.crossOriginIsolated ? "./pkg" : "./pkg-single-threaded";
 
 onmessage = async (event) => {
-    const entropy = event.data;
-    console.log("available threads:", navigator.hardwareConcurrency);
+    const { entropy, mouseSamples } = event.data;
+    console.log("package:", pkg, "available threads:", navigator.hardwareConcurrency);
 
+    const {
+        default: init,
+        init_threads,
+        contribute_wasm,
+        subgroup_check_wasm,
+        get_pot_pubkeys_wasm,
+        mix_entropy_wasm,
+        verify_wasm,
+    } = await import(`${pkg}/wrapper_small_pot.js`);
     await init();
+    // a no-op in the single-threaded package
     await init_threads(navigator.hardwareConcurrency);
 
     fetch('./initialContribution.json').then(response => {
         response.json().then(async (data) => {
             const json_string = JSON.stringify(data);
-            let secret = await sha256(entropy);
             let identity = "eth|0x000000000000000000000000000000000000dead";
 
-            console.log("get potPubkeys from entropy");
-            const potPubkeys = get_pot_pubkeys_wasm(secret);
-            console.log(potPubkeys);
+            try {
+                // typed text, mouse movements and browser randomness
+                const secret = mix_entropy_wasm(entropy, mouseSamples);
 
-            console.log("start contribution");
-            const startTime = performance.now();
-            const result_string = contribute_wasm(
-                json_string,
-                secret,
-                identity,
-            );
-            const endTime = performance.now();
-            const result = JSON.parse(result_string);
-            console.log(result)
-            console.log(`Contribution took ${endTime - startTime} milliseconds`);
+                console.log("get potPubkeys from entropy");
+                const potPubkeys = get_pot_pubkeys_wasm(secret);
+                console.log(potPubkeys);
 
-            console.log("perform subgroups checks in previous and new contribution");
-            // check initial contribution
-            const checkInitialContribution = subgroup_check_wasm(json_string);
-            console.log(checkInitialContribution)
-            // check updated contribution
-            const checkUpdatedContribution = subgroup_check_wasm(result_string);
-            console.log(checkUpdatedContribution)
+                console.log("start contribution");
+                const startTime = performance.now();
+                const result_string = contribute_wasm(
+                    json_string,
+                    secret,
+                    identity,
+                );
+                const endTime = performance.now();
+                const result = JSON.parse(result_string);
+                console.log(result)
+                console.log(`Contribution took ${endTime - startTime} milliseconds`);
+
+                console.log("perform subgroups checks in previous and new contribution");
+                // both throw when the contribution is invalid
+                subgroup_check_wasm(json_string);
+                subgroup_check_wasm(result_string);
+                console.log("Subgroup checks are correct")
+            } catch (error) {
+                logError(error);
+            }
         });
     });
 
     fetch('./transcript.json').then(response => {
         response.json().then(async (data) => {
             const json_string = JSON.stringify(data);
-            const startTime = performance.now();
-            const verify = verify_wasm(json_string);
-            const endTime = performance.now();
-            console.log(`Verify is: ${verify}`);
-            console.log(`Verification took ${endTime - startTime} milliseconds`);
+            try {
+                const startTime = performance.now();
+                // throws when the transcript is invalid
+                verify_wasm(json_string);
+                const endTime = performance.now();
+                console.log("Verification is correct");
+                console.log(`Verification took ${endTime - startTime} milliseconds`);
+            } catch (error) {
+                logError(error);
+            }
         });
     });
 }
 
-async function sha256(message) {
-    // encode as UTF-8
-    const msgBuffer = new TextEncoder().encode(message);
-    // hash the message
-    const hashBuffer = await crypto.subtle.digest('SHA-256', msgBuffer);
-    // convert ArrayBuffer to Array
-    const hashArray = Array.from(new Uint8Array(hashBuffer));
-    // convert bytes to hex string
-    const hashHex = hashArray.map(b => b.toString(16).padStart(2, '0')).join('');
-    return hashHex;
+function logError(error) {
+    // errors thrown by the wasm functions carry a stable `code`
+    // and, when relevant, the `subCeremony` index
+    console.error(`[${error.code}] ${error.message}`, error.subCeremony ?? "");
 }